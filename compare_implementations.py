@@ -327,6 +327,7 @@ def run_rust_simulation(config: Dict[str, Any]) -> Dict[str, Any]:
     binary_path = "wasm-sim/target/release/blockchain-sim"
     cmd = [
         binary_path,
+        "--python-reference",  # Match the script's constants and M_N rule
         "--n", str(config["n"]),
         "--run-type", str(config["run_type"]),
         "--large-sim-mode", str(int(config["large_sim_mode"])),
@@ -0,0 +1,104 @@
+//! Running many configs at once
+//!
+//! Runs share nothing but their inputs, so a batch is spread over scoped
+//! threads that take the next config from a shared counter. Results come
+//! back in config order, and each run's random streams depend only on its
+//! own `seed`, so a batch gives the same results with any thread count. On
+//! wasm32 there are no threads and the batch runs sequentially.
+
+use crate::lib_core::{run_simulation_with_inputs, splitmix64, SimulationConfig, SimulationInputs, SimulationResults};
+
+/// Threads to use when none are requested: one per available core
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Seed of run `run` of an ensemble started from `base_seed`
+pub fn ensemble_seed(base_seed: u64, run: usize) -> u64 {
+    splitmix64(base_seed.wrapping_add(run as u64 + 1)).max(1)
+}
+
+/// Run every config on the same inputs with up to `threads` threads (0 =
+/// one per core)
+pub fn run_batch(configs: &[SimulationConfig], inputs: &SimulationInputs, threads: usize) -> Vec<SimulationResults> {
+    run_batch_map(configs, inputs, threads, |_, results| results)
+}
+
+/// `runs` copies of `config` with distinct seeds derived from `base_seed`
+pub fn ensemble_configs(config: &SimulationConfig, runs: usize, base_seed: u64) -> Vec<SimulationConfig> {
+    (0..runs)
+        .map(|run| SimulationConfig { seed: ensemble_seed(base_seed, run), ..*config })
+        .collect()
+}
+
+/// Run an ensemble of `config`, see `ensemble_configs`
+pub fn run_ensemble(
+    config: &SimulationConfig,
+    inputs: &SimulationInputs,
+    runs: usize,
+    base_seed: u64,
+    threads: usize,
+) -> Vec<SimulationResults> {
+    run_batch(&ensemble_configs(config, runs, base_seed), inputs, threads)
+}
+
+/// Like `run_batch`, but each run's results are passed through `map` (with
+/// the config's index) on the worker, so large batches can keep just the
+/// numbers they need
+pub fn run_batch_map<T, F>(configs: &[SimulationConfig], inputs: &SimulationInputs, threads: usize, map: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize, SimulationResults) -> T + Sync,
+{
+    parallel_map(configs, threads, |k, &config| map(k, run_simulation_with_inputs(config, inputs)))
+}
+
+/// `work` applied to every item (with its index) on up to `threads` threads
+/// (0 = one per core), results in item order
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parallel_map<I, T, F>(items: &[I], threads: usize, work: F) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(usize, &I) -> T + Sync,
+{
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let threads = if threads == 0 { default_threads() } else { threads }.min(items.len());
+    if threads <= 1 {
+        return items.iter().enumerate().map(|(k, item)| work(k, item)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, T)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let k = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(k) else { break };
+                        done.push((k, work(k, item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    done.sort_unstable_by_key(|&(k, _)| k);
+    done.into_iter().map(|(_, value)| value).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn parallel_map<I, T, F>(items: &[I], _threads: usize, work: F) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(usize, &I) -> T + Sync,
+{
+    items.iter().enumerate().map(|(k, item)| work(k, item)).collect()
+}
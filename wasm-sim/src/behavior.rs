@@ -0,0 +1,258 @@
+//! Fee-bumping behavior models: how users choose between the two fee tiers
+
+use crate::lib_core::SimulationConfig;
+
+/// Which behavior model drives tier selection, with its tunable parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeeBehaviorModel {
+    /// Everyone broadcasts at the low tier
+    None,
+    /// Original USERS_PAY_MORE rule: the share of new tx paying the high tier
+    /// moves `response_rate` of the way toward `mempool[1] / (backlog_divisor * broadcast[1])`
+    PayMore { response_rate: f64, backlog_divisor: f64 },
+    /// Low-tier tx left in the mempool for more than `patience_blocks` blocks
+    /// are re-broadcast at the high tier, `bump_fraction` of the queue per block
+    ReplaceByFee { patience_blocks: u32, bump_fraction: f64 },
+    /// A share `adoption` of users pick the tier using the WALLET_CALC fee
+    /// estimates from the original script
+    WalletEstimate { adoption: f64 },
+    /// `impatient_share` of users pay the high tier whenever the low tier is
+    /// backlogged; the rest wait `patience_blocks` blocks before bumping
+    PopulationMix { impatient_share: f64, patience_blocks: u32 },
+}
+
+impl FeeBehaviorModel {
+    /// Build a model from a numeric kind and two generic parameters, as used by
+    /// the CLI and the WASM wrapper. Negative (or NaN) parameters select the
+    /// model default, so 0 can be set explicitly; the backlog divisor must be
+    /// positive.
+    ///
+    /// Kinds: 0 = none, 1 = pay more, 2 = replace-by-fee, 3 = wallet estimate,
+    /// 4 = population mix.
+    pub fn from_parts(kind: u32, param_a: f64, param_b: f64) -> Self {
+        let or = |value: f64, default: f64| if value >= 0.0 { value } else { default };
+        match kind {
+            1 => FeeBehaviorModel::PayMore {
+                response_rate: or(param_a, 0.1),
+                backlog_divisor: if param_b > 0.0 { param_b } else { 3.0 },
+            },
+            2 => FeeBehaviorModel::ReplaceByFee {
+                patience_blocks: or(param_a, 10.0) as u32,
+                bump_fraction: or(param_b, 0.1),
+            },
+            3 => FeeBehaviorModel::WalletEstimate {
+                adoption: or(param_a, 1.0),
+            },
+            4 => FeeBehaviorModel::PopulationMix {
+                impatient_share: or(param_a, 0.2),
+                patience_blocks: or(param_b, 30.0) as u32,
+            },
+            _ => FeeBehaviorModel::None,
+        }
+    }
+
+    /// Model in effect for a config, honouring the legacy `users_pay_more` flag
+    pub fn resolve(config: &SimulationConfig) -> Self {
+        match config.fee_behavior {
+            FeeBehaviorModel::None if config.users_pay_more => FeeBehaviorModel::from_parts(1, -1.0, -1.0),
+            model => model,
+        }
+    }
+}
+
+/// Network state a behavior model can react to when new tx are broadcast
+pub struct BehaviorContext {
+    pub t_sim: i64,
+    pub z_m: i64,
+    pub t_r: i64,
+    pub r_base: f64,
+    pub m_l: i64,
+    pub m_n: i64,
+    pub m_b_max: i64,
+    /// Fee per tx paid at each tier, high tier first
+    pub fees: [f64; 2],
+}
+
+/// A rule for splitting broadcast tx between the fee tiers.
+///
+/// `respond` is called once per block after the new volume has been placed in
+/// `broadcast[1]` and before it is added to the mempool. Implementations may
+/// move volume into `broadcast[0]` and re-tier tx already queued in `mempool`.
+pub trait FeeBehavior {
+    fn respond(&mut self, ctx: &BehaviorContext, broadcast: &mut [i64; 2], mempool: &mut [i64; 2]);
+
+    /// Percentage of tx the model moved to the high tier in the last block
+    fn percent_response(&self) -> f64;
+}
+
+/// Instantiate the behavior model for a config, if any
+pub fn build_behavior(config: &SimulationConfig) -> Option<Box<dyn FeeBehavior>> {
+    match FeeBehaviorModel::resolve(config) {
+        FeeBehaviorModel::None => None,
+        FeeBehaviorModel::PayMore { response_rate, backlog_divisor } => Some(Box::new(PayMore {
+            response_rate,
+            backlog_divisor,
+            percent_response: 0.0,
+        })),
+        FeeBehaviorModel::ReplaceByFee { patience_blocks, bump_fraction } => Some(Box::new(ReplaceByFee {
+            patience_blocks,
+            bump_fraction: bump_fraction.clamp(0.0, 1.0),
+            backlog_age: 0,
+            percent_response: 0.0,
+        })),
+        FeeBehaviorModel::WalletEstimate { adoption } => Some(Box::new(WalletEstimate {
+            adoption: adoption.clamp(0.0, 1.0),
+            percent_response: 0.0,
+        })),
+        FeeBehaviorModel::PopulationMix { impatient_share, patience_blocks } => Some(Box::new(PopulationMix {
+            impatient_share: impatient_share.clamp(0.0, 1.0),
+            patience: ReplaceByFee {
+                patience_blocks,
+                bump_fraction: 1.0 / patience_blocks.max(1) as f64,
+                backlog_age: 0,
+                percent_response: 0.0,
+            },
+            percent_response: 0.0,
+        })),
+    }
+}
+
+/// Whether the low tier holds more than one block of M_B_max
+fn low_tier_backlogged(ctx: &BehaviorContext, mempool: &[i64; 2]) -> bool {
+    mempool[1] * ctx.t_sim > ctx.m_b_max
+}
+
+/// Move `percent` of the low-tier broadcast to the high tier
+fn split_broadcast(broadcast: &mut [i64; 2], percent: f64) {
+    if percent > 0.0 {
+        broadcast[0] = ((broadcast[1] as f64 * percent) / 100.0) as i64;
+        broadcast[1] = ((broadcast[1] as f64 * (100.0 - percent)) / 100.0) as i64;
+    }
+}
+
+pub struct PayMore {
+    response_rate: f64,
+    backlog_divisor: f64,
+    percent_response: f64,
+}
+
+impl FeeBehavior for PayMore {
+    fn respond(&mut self, _ctx: &BehaviorContext, broadcast: &mut [i64; 2], mempool: &mut [i64; 2]) {
+        if broadcast[1] <= 0 {
+            return;
+        }
+        let prev_resp = self.percent_response;
+        let calc = (mempool[1] as f64 / (self.backlog_divisor * broadcast[1] as f64) * 100.0).floor();
+        self.percent_response = (prev_resp + self.response_rate * (calc - prev_resp)).floor();
+        self.percent_response = self.percent_response.clamp(0.0, 100.0);
+
+        split_broadcast(broadcast, self.percent_response);
+    }
+
+    fn percent_response(&self) -> f64 {
+        self.percent_response
+    }
+}
+
+pub struct ReplaceByFee {
+    patience_blocks: u32,
+    bump_fraction: f64,
+    /// Consecutive blocks the low tier has carried more than one full block
+    backlog_age: u32,
+    percent_response: f64,
+}
+
+impl FeeBehavior for ReplaceByFee {
+    fn respond(&mut self, ctx: &BehaviorContext, _broadcast: &mut [i64; 2], mempool: &mut [i64; 2]) {
+        if low_tier_backlogged(ctx, mempool) {
+            self.backlog_age += 1;
+        } else {
+            self.backlog_age = 0;
+        }
+
+        self.percent_response = 0.0;
+        if self.backlog_age > self.patience_blocks && mempool[1] > 0 {
+            let bumped = ((mempool[1] as f64 * self.bump_fraction).ceil() as i64).min(mempool[1]);
+            mempool[1] -= bumped;
+            mempool[0] += bumped;
+            self.percent_response = 100.0 * bumped as f64 / (mempool[0] + mempool[1]).max(1) as f64;
+        }
+    }
+
+    fn percent_response(&self) -> f64 {
+        self.percent_response
+    }
+}
+
+pub struct WalletEstimate {
+    adoption: f64,
+    percent_response: f64,
+}
+
+impl WalletEstimate {
+    /// Wallet fee levels per byte (low, normal, medium, high) from WALLET_CALC
+    fn fee_levels(ctx: &BehaviorContext) -> [f64; 4] {
+        let m_lw = ctx.m_l.max(1) as f64;
+        let m_nw = ctx.m_n.max(1) as f64;
+        let b_rlw = ctx.t_r as f64 / m_lw;
+        let b_r = ctx.t_r as f64 / ctx.z_m.max(1) as f64;
+
+        let f_l = ctx.r_base * b_rlw / m_lw;
+        let f_n = 4.0 * f_l;
+        let f_m = 16.0 * ctx.r_base * b_r / m_lw;
+        let f_h = 4.0 * f_m * (m_lw / (32.0 * b_r * m_nw)).max(1.0);
+        [f_l, f_n, f_m, f_h]
+    }
+}
+
+impl FeeBehavior for WalletEstimate {
+    fn respond(&mut self, ctx: &BehaviorContext, broadcast: &mut [i64; 2], mempool: &mut [i64; 2]) {
+        // Block the new tx would land in if it joined the back of the queue
+        let queued = ((mempool[0] + mempool[1] + broadcast[1]) * ctx.t_sim).min(ctx.m_b_max);
+        let m_n = ctx.m_n.max(1) as f64;
+        let b = (queued as f64 / m_n - 1.0).max(0.0);
+        // Marginal penalty per byte at that block size
+        let needed = 2.0 * ctx.r_base * b / m_n;
+
+        // Cheapest wallet level covering the penalty, compared with the low tier
+        let levels = Self::fee_levels(ctx);
+        let chosen = levels.iter().copied().find(|&f| f >= needed).unwrap_or(levels[3]);
+        let low_tier = ctx.fees[1] / ctx.t_sim as f64;
+
+        self.percent_response = if chosen > low_tier && needed > 0.0 {
+            (100.0 * self.adoption).floor()
+        } else {
+            0.0
+        };
+        split_broadcast(broadcast, self.percent_response);
+    }
+
+    fn percent_response(&self) -> f64 {
+        self.percent_response
+    }
+}
+
+pub struct PopulationMix {
+    impatient_share: f64,
+    patience: ReplaceByFee,
+    percent_response: f64,
+}
+
+impl FeeBehavior for PopulationMix {
+    fn respond(&mut self, ctx: &BehaviorContext, broadcast: &mut [i64; 2], mempool: &mut [i64; 2]) {
+        // Patient users already queued bump once they run out of patience
+        let backlogged = low_tier_backlogged(ctx, mempool);
+        self.patience.respond(ctx, broadcast, mempool);
+
+        self.percent_response = if backlogged {
+            (100.0 * self.impatient_share).floor()
+        } else {
+            0.0
+        };
+        split_broadcast(broadcast, self.percent_response);
+    }
+
+    fn percent_response(&self) -> f64 {
+        self.percent_response
+    }
+}
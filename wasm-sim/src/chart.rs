@@ -0,0 +1,431 @@
+//! SVG line charts of a run, so headless CLI runs can produce figures
+//!
+//! Mirrors the web UI: one chart per quantity plus the combined chart, whose
+//! series are multiplied by the same `scale_*` factors. Each series is cut
+//! down to the min and max of every pixel column, so spikes survive on long
+//! runs without writing every block.
+
+use crate::lib_core::SimulationResults;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub label: String,
+    pub color: &'static str,
+    pub values: Vec<f64>,
+    pub axis: Axis,
+}
+
+#[derive(Clone, Debug)]
+pub struct Chart {
+    pub title: String,
+    /// Block height of each value
+    pub x: Vec<f64>,
+    pub series: Vec<Series>,
+    pub y_label: String,
+    pub y1_label: String,
+    /// Logarithmic y axes; values <= 0 are left out
+    pub log_scale: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Multipliers applied to the combined chart, as the UI's scale controls
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChartScales {
+    pub block_size: f64,
+    pub txpool: f64,
+    pub volume: f64,
+    pub fees: f64,
+    pub penalty: f64,
+}
+
+impl Default for ChartScales {
+    fn default() -> Self {
+        ChartScales {
+            block_size: 1.0,
+            txpool: 1.0,
+            volume: 1.0,
+            fees: 1.0,
+            penalty: 1000.0,
+        }
+    }
+}
+
+/// The charts of the UI
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartKind {
+    /// M_B with the M_L, M_S and M_N medians
+    Sizes,
+    Mempool,
+    Volume,
+    Fees,
+    Penalty,
+    Combined,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 6] = [
+        ChartKind::Sizes,
+        ChartKind::Mempool,
+        ChartKind::Volume,
+        ChartKind::Fees,
+        ChartKind::Penalty,
+        ChartKind::Combined,
+    ];
+
+    pub fn parse(name: &str) -> Option<ChartKind> {
+        ChartKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChartKind::Sizes => "sizes",
+            ChartKind::Mempool => "mempool",
+            ChartKind::Volume => "volume",
+            ChartKind::Fees => "fees",
+            ChartKind::Penalty => "penalty",
+            ChartKind::Combined => "combined",
+        }
+    }
+
+    pub fn chart(self, results: &SimulationResults, scales: &ChartScales, log_scale: bool) -> Chart {
+        let ints = |data: &[i64], scale: f64| -> Vec<f64> { data.iter().map(|&v| v as f64 * scale).collect() };
+        let floats = |data: &[f64], scale: f64| -> Vec<f64> { data.iter().map(|&v| v * scale).collect() };
+        let series = |label: &str, color, values, axis| Series { label: label.to_string(), color, values, axis };
+        let scaled = |label: &str, scale: f64| format!("{} (×{})", label, scale);
+
+        let (title, y_label, y1_label, series) = match self {
+            ChartKind::Sizes => (
+                "Block Size and Medians",
+                "Bytes",
+                "",
+                vec![
+                    series("M_B", "#0dcaf0", ints(&results.M_B, 1.0), Axis::Left),
+                    series("M_L", "#6f42c1", ints(&results.M_L, 1.0), Axis::Left),
+                    series("M_S", "#20c997", ints(&results.M_S, 1.0), Axis::Left),
+                    series("M_N", "#e83e8c", ints(&results.M_N, 1.0), Axis::Left),
+                ],
+            ),
+            ChartKind::Mempool => (
+                "TxPool Size",
+                "Bytes",
+                "",
+                vec![series("TxPool Size", "#fd7e14", ints(&results.mempool_size, 1.0), Axis::Left)],
+            ),
+            ChartKind::Volume => (
+                "Broadcast Volume",
+                "Bytes",
+                "",
+                vec![series("Broadcast Vol", "#198754", ints(&results.input_volume, 1.0), Axis::Left)],
+            ),
+            ChartKind::Fees => (
+                "Total Fees per Block",
+                "XMR",
+                "",
+                vec![series("Total Fees", "#0d6efd", floats(&results.block_fee, 1.0), Axis::Left)],
+            ),
+            ChartKind::Penalty => (
+                "Penalty",
+                "Fraction of block reward",
+                "",
+                vec![series("Penalty", "#dc3545", floats(&results.penalty, 1.0), Axis::Left)],
+            ),
+            ChartKind::Combined => (
+                "Combined Analysis",
+                "Bytes (Size/Vol)",
+                "Ratio / Atomic Units",
+                vec![
+                    series(&scaled("Block Size", scales.block_size), "#0dcaf0", ints(&results.M_B, scales.block_size), Axis::Left),
+                    series(&scaled("TxPool Size", scales.txpool), "#fd7e14", ints(&results.mempool_size, scales.txpool), Axis::Left),
+                    series(&scaled("Volume", scales.volume), "#198754", ints(&results.input_volume, scales.volume), Axis::Left),
+                    series(&scaled("Fees", scales.fees), "#0d6efd", floats(&results.block_fee, scales.fees), Axis::Right),
+                    series(&scaled("Penalty", scales.penalty), "#dc3545", floats(&results.penalty, scales.penalty), Axis::Right),
+                ],
+            ),
+        };
+
+        Chart {
+            title: title.to_string(),
+            x: results.indices.iter().map(|&i| i as f64).collect(),
+            series,
+            y_label: y_label.to_string(),
+            y1_label: y1_label.to_string(),
+            log_scale,
+            width: 900,
+            height: 400,
+        }
+    }
+}
+
+/// Write one `<name>.svg` per chart kind into `dir`; kinds in `log_scale` get
+/// logarithmic y axes
+pub fn write_svg_charts(
+    dir: &Path,
+    results: &SimulationResults,
+    scales: &ChartScales,
+    log_scale: &[ChartKind],
+) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    ChartKind::ALL
+        .iter()
+        .map(|&kind| {
+            let path = dir.join(format!("{}.svg", kind.name()));
+            let svg = kind.chart(results, scales, log_scale.contains(&kind)).to_svg();
+            std::fs::write(&path, svg).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Value range of one y axis
+struct YScale {
+    low: f64,
+    high: f64,
+    /// Tick spacing (linear axes only)
+    step: f64,
+    log: bool,
+}
+
+impl YScale {
+    fn fit<'a>(values: impl Iterator<Item = &'a f64>, log: bool) -> YScale {
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        for &v in values.filter(|v| v.is_finite() && (!log || **v > 0.0)) {
+            min = min.min(v);
+            max = max.max(v);
+        }
+        if log {
+            if min > max {
+                return YScale { low: 1.0, high: 10.0, step: 0.0, log };
+            }
+            let low = 10f64.powf(min.log10().floor());
+            let high = 10f64.powf(max.log10().ceil()).max(low * 10.0);
+            return YScale { low, high, step: 0.0, log };
+        }
+        if min > max {
+            (min, max) = (0.0, 1.0);
+        }
+        let min = min.min(0.0);
+        let max = if max > min { max } else { min + 1.0 };
+        let step = nice_step((max - min) / 5.0);
+        YScale { low: (min / step).floor() * step, high: (max / step).ceil() * step, step, log }
+    }
+
+    /// Position of `v` from the bottom (0) to the top (1) of the axis
+    fn position(&self, v: f64) -> Option<f64> {
+        if !v.is_finite() {
+            None
+        } else if self.log {
+            (v > 0.0).then(|| (v.log10() - self.low.log10()) / (self.high.log10() - self.low.log10()))
+        } else {
+            Some((v - self.low) / (self.high - self.low))
+        }
+    }
+
+    fn ticks(&self) -> Vec<f64> {
+        if self.log {
+            let decades = (self.high.log10() - self.low.log10()).round() as i32;
+            let every = (decades + 7) / 8; // at most ~8 labels
+            (0..=decades).step_by(every.max(1) as usize).map(|d| self.low * 10f64.powi(d)).collect()
+        } else {
+            let count = ((self.high - self.low) / self.step).round() as i32;
+            (0..=count).map(|k| self.low + k as f64 * self.step).collect()
+        }
+    }
+}
+
+/// 1, 2 or 5 times a power of ten, at least `raw`
+fn nice_step(raw: f64) -> f64 {
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let fraction = raw / magnitude;
+    let nice = if fraction <= 1.0 {
+        1.0
+    } else if fraction <= 2.0 {
+        2.0
+    } else if fraction <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// Short tick label: 1.5M, 250k, 0.5, 2e-5
+fn format_tick(v: f64) -> String {
+    let trim = |s: String| -> String {
+        if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.').to_string() } else { s }
+    };
+    let a = v.abs();
+    if a == 0.0 {
+        "0".to_string()
+    } else if a >= 1e9 {
+        trim(format!("{:.2}", v / 1e9)) + "G"
+    } else if a >= 1e6 {
+        trim(format!("{:.2}", v / 1e6)) + "M"
+    } else if a >= 1e3 {
+        trim(format!("{:.2}", v / 1e3)) + "k"
+    } else if a >= 0.01 {
+        trim(format!("{:.2}", v))
+    } else {
+        let s = format!("{:.1e}", v);
+        match s.split_once('e') {
+            Some((mantissa, exponent)) => format!("{}e{}", trim(mantissa.to_string()), exponent),
+            None => s,
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl Chart {
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let has_right = self.series.iter().any(|s| s.axis == Axis::Right);
+        let (left, right, top, bottom) = (70.0, if has_right { 70.0 } else { 20.0 }, 40.0, 40.0);
+        let plot_w = (width - left - right).max(1.0);
+        let plot_h = (height - top - bottom).max(1.0);
+
+        let x_min = self.x.first().copied().unwrap_or(0.0);
+        let x_max = self.x.last().copied().unwrap_or(1.0).max(x_min + 1.0);
+        let px = |x: f64| left + (x - x_min) / (x_max - x_min) * plot_w;
+        let axis_scale = |axis: Axis| {
+            let values = self.series.iter().filter(|s| s.axis == axis).flat_map(|s| s.values.iter());
+            YScale::fit(values, self.log_scale)
+        };
+        let y_scales = [axis_scale(Axis::Left), axis_scale(Axis::Right)];
+        let py = |scale: &YScale, v: f64| scale.position(v).map(|p| top + (1.0 - p.clamp(0.0, 1.0)) * plot_h);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="11">"#,
+            self.width, self.height
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="22" text-anchor="middle" font-size="14" font-weight="bold">{}</text>"#,
+            width / 2.0,
+            escape(&self.title)
+        );
+
+        // Grid and tick labels; the right axis only gets labels
+        for (k, scale) in y_scales.iter().enumerate() {
+            if k == 1 && !has_right {
+                continue;
+            }
+            for tick in scale.ticks() {
+                let Some(y) = py(scale, tick) else { continue };
+                if k == 0 {
+                    let _ = writeln!(
+                        svg,
+                        r##"<line x1="{}" y1="{y:.1}" x2="{}" y2="{y:.1}" stroke="#e0e0e0"/>"##,
+                        left,
+                        left + plot_w
+                    );
+                }
+                let (x, anchor) = if k == 0 { (left - 6.0, "end") } else { (left + plot_w + 6.0, "start") };
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{:.1}" text-anchor="{}" dominant-baseline="middle">{}</text>"#,
+                    x,
+                    y,
+                    anchor,
+                    format_tick(tick)
+                );
+            }
+        }
+        let x_step = nice_step((x_max - x_min) / 8.0);
+        let mut tick = (x_min / x_step).ceil() * x_step;
+        while tick <= x_max {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{}" text-anchor="middle">{}</text>"#,
+                px(tick),
+                top + plot_h + 16.0,
+                format_tick(tick)
+            );
+            tick += x_step;
+        }
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#888"/>"##,
+            left, top, plot_w, plot_h
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">Block</text>"#,
+            left + plot_w / 2.0,
+            height - 6.0
+        );
+        for (label, x, rotate) in [(&self.y_label, 14.0, -90), (&self.y1_label, width - 14.0, 90)] {
+            if label.is_empty() || (rotate > 0 && !has_right) {
+                continue;
+            }
+            let y = top + plot_h / 2.0;
+            let _ = writeln!(
+                svg,
+                r#"<text x="{x}" y="{y}" text-anchor="middle" transform="rotate({rotate} {x} {y})">{}</text>"#,
+                escape(label)
+            );
+        }
+
+        // Series, as min/max per pixel column
+        let columns = (plot_w as usize).max(1);
+        for series in &self.series {
+            let scale = &y_scales[if series.axis == Axis::Left { 0 } else { 1 }];
+            let n = series.values.len().min(self.x.len());
+            let mut d = String::new();
+            let mut pen_down = false;
+            let buckets = columns.min(n);
+            for c in 0..buckets {
+                let (from, to) = (c * n / buckets, (c + 1) * n / buckets);
+                let bucket = &series.values[from..to.max(from + 1)];
+                let lowest = (0..bucket.len()).min_by(|&a, &b| bucket[a].total_cmp(&bucket[b])).unwrap_or(0);
+                let highest = (0..bucket.len()).max_by(|&a, &b| bucket[a].total_cmp(&bucket[b])).unwrap_or(0);
+                let mut points = [lowest, highest];
+                points.sort_unstable();
+                for k in points {
+                    match py(scale, bucket[k]) {
+                        Some(y) => {
+                            let _ = write!(d, "{}{:.1},{:.1} ", if pen_down { "L" } else { "M" }, px(self.x[from + k]), y);
+                            pen_down = true;
+                        }
+                        None => pen_down = false,
+                    }
+                }
+            }
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+                d.trim_end(),
+                series.color
+            );
+        }
+
+        // Legend
+        for (k, series) in self.series.iter().enumerate() {
+            let y = top + 12.0 + 14.0 * k as f64;
+            let x = left + 10.0;
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{x}" y1="{y}" x2="{}" y2="{y}" stroke="{}" stroke-width="2"/><text x="{}" y="{y}" dominant-baseline="middle">{}</text>"#,
+                x + 16.0,
+                series.color,
+                x + 20.0,
+                escape(&series.label)
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,113 @@
+//! A/B comparison of two configs on the same inputs
+//!
+//! Both runs get the same demand trace, window seed, network and events, and
+//! the engine's random streams use fixed seeds, so with matching demand
+//! settings the only difference between the runs is the config change.
+
+use crate::history::SeriesError;
+use crate::batch::run_batch;
+use crate::lib_core::{SimulationConfig, SimulationInputs, SimulationResults};
+
+/// How far a series of run B strays from run A
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SeriesDiff {
+    /// Error of B against A
+    pub error: SeriesError,
+    /// Height of the largest gap
+    pub max_at: u32,
+}
+
+impl SeriesDiff {
+    fn between(a: &[i64], b: &[i64], indices: &[u32]) -> SeriesDiff {
+        let max_at = a
+            .iter()
+            .zip(b)
+            .enumerate()
+            .rev() // earliest of equal gaps
+            .max_by_key(|(_, (a, b))| (**b - **a).abs())
+            .map_or(0, |(k, _)| indices.get(k).copied().unwrap_or(k as u32));
+        SeriesDiff { error: SeriesError::between(b, a), max_at }
+    }
+}
+
+/// First height at which M_B reached a size in each run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThresholdDelta {
+    pub threshold: i64,
+    pub a: Option<u32>,
+    pub b: Option<u32>,
+}
+
+impl ThresholdDelta {
+    fn new(threshold: i64, a: &SimulationResults, b: &SimulationResults) -> ThresholdDelta {
+        let first = |r: &SimulationResults| r.M_B.iter().position(|&m_b| m_b >= threshold).map(|k| r.indices[k]);
+        ThresholdDelta { threshold, a: first(a), b: first(b) }
+    }
+
+    /// Blocks B reached the threshold after A (negative if earlier)
+    pub fn delta(&self) -> Option<i64> {
+        Some(self.b? as i64 - self.a? as i64)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub a: SimulationResults,
+    pub b: SimulationResults,
+    /// Blocks both runs cover; the series below are aligned over them
+    pub blocks: usize,
+    /// M_B of B minus M_B of A, per block
+    pub m_b_delta: Vec<i64>,
+    /// Block fee of B minus block fee of A, per block
+    pub fee_delta: Vec<f64>,
+    pub m_b: SeriesDiff,
+    pub m_l: SeriesDiff,
+    pub m_s: SeriesDiff,
+    pub m_n: SeriesDiff,
+    pub mempool: SeriesDiff,
+    pub thresholds: Vec<ThresholdDelta>,
+    /// Sum of per-block penalties of each run
+    pub penalty_total: [f64; 2],
+}
+
+impl Comparison {
+    pub fn fees_total(&self) -> [f64; 2] {
+        [self.a.cumulative_fees, self.b.cumulative_fees]
+    }
+}
+
+/// Compare two configs with no extra inputs, timing thresholds at 2x, 5x and
+/// 10x A's steady state
+pub fn compare(config_a: SimulationConfig, config_b: SimulationConfig) -> Comparison {
+    let thresholds = [2, 5, 10].map(|k| k * config_a.steady_state);
+    compare_with_inputs(config_a, config_b, &SimulationInputs::default(), &thresholds)
+}
+
+pub fn compare_with_inputs(
+    config_a: SimulationConfig,
+    config_b: SimulationConfig,
+    inputs: &SimulationInputs,
+    thresholds: &[i64],
+) -> Comparison {
+    let [a, b]: [SimulationResults; 2] = run_batch(&[config_a, config_b], inputs, 2)
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("a batch returns one result per config"));
+    let blocks = a.M_B.len().min(b.M_B.len());
+    let indices = &a.indices[..blocks];
+    let diff = |x: &[i64], y: &[i64]| SeriesDiff::between(&x[..blocks], &y[..blocks], indices);
+
+    Comparison {
+        blocks,
+        m_b_delta: a.M_B.iter().zip(&b.M_B).map(|(a, b)| b - a).collect(),
+        fee_delta: a.block_fee.iter().zip(&b.block_fee).map(|(a, b)| b - a).collect(),
+        m_b: diff(&a.M_B, &b.M_B),
+        m_l: diff(&a.M_L, &b.M_L),
+        m_s: diff(&a.M_S, &b.M_S),
+        m_n: diff(&a.M_N, &b.M_N),
+        mempool: diff(&a.mempool_size, &b.mempool_size),
+        thresholds: thresholds.iter().map(|&t| ThresholdDelta::new(t, &a, &b)).collect(),
+        penalty_total: [a.penalty.iter().sum(), b.penalty.iter().sum()],
+        a,
+        b,
+    }
+}
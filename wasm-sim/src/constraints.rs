@@ -0,0 +1,134 @@
+//! Which constraint bound each block: the terms of
+//! `M_B_max = min(2*M_N, 16*M_L, A_C)`, or the fee/demand side when the block
+//! stopped short of M_B_max, plus the clamp applied to the M_L weight
+
+use crate::lib_core::{RuleSet, SimulationConfig};
+
+/// What limited the size of a block
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BlockLimit {
+    /// Block reached M_B_max and 2*M_N was the smallest term
+    TwoMn = 0,
+    /// Block reached M_B_max and the M_L term (`ml_cap_mult`*M_L) was the smallest
+    LongTermCap = 1,
+    /// Block reached M_B_max and the sanity cap A_C was the smallest term
+    SanityCap = 2,
+    /// Transactions were left in the mempool because their fee didn't cover
+    /// the marginal penalty (and orphan risk)
+    Fee = 3,
+    /// The block took everything in the mempool
+    Demand = 4,
+}
+
+impl BlockLimit {
+    pub const ALL: [BlockLimit; 5] = [
+        BlockLimit::TwoMn,
+        BlockLimit::LongTermCap,
+        BlockLimit::SanityCap,
+        BlockLimit::Fee,
+        BlockLimit::Demand,
+    ];
+
+    /// Classify a block of `m_b` bytes built under `m_b_max`, with `backlog`
+    /// transactions left in the mempool afterwards
+    pub fn classify(
+        config: &SimulationConfig,
+        m_n: i64,
+        m_l: i64,
+        sanity_cap: i64,
+        m_b: i64,
+        m_b_max: i64,
+        backlog: i64,
+    ) -> BlockLimit {
+        if m_b >= m_b_max {
+            let long_term_cap = config.long_term_cap(m_l);
+            match config.rule_set {
+                RuleSet::Legacy => BlockLimit::TwoMn,
+                RuleSet::Current if sanity_cap <= (2 * m_n).min(long_term_cap) => BlockLimit::SanityCap,
+                RuleSet::Current if long_term_cap < 2 * m_n => BlockLimit::LongTermCap,
+                RuleSet::Current => BlockLimit::TwoMn,
+            }
+        } else if backlog > 0 {
+            BlockLimit::Fee
+        } else {
+            BlockLimit::Demand
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BlockLimit::TwoMn => "2*M_N",
+            BlockLimit::LongTermCap => "M_L cap",
+            BlockLimit::SanityCap => "A_C",
+            BlockLimit::Fee => "fee",
+            BlockLimit::Demand => "demand",
+        }
+    }
+}
+
+/// Which bound of `clamp(M_B, M_L/ml_mult, ml_mult*M_L)` (with the `z_m`
+/// floor) set the weight pushed into the long term window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MlClamp {
+    /// M_B went in unchanged
+    Free = 0,
+    /// Cut down to ml_mult*M_L
+    Upper = 1,
+    /// Raised to z_m
+    Floor = 2,
+    /// Raised to M_L/ml_mult
+    Lower = 3,
+}
+
+impl MlClamp {
+    pub const ALL: [MlClamp; 4] = [MlClamp::Free, MlClamp::Upper, MlClamp::Floor, MlClamp::Lower];
+
+    /// Mirrors `m_b.min(upper).max(z_m).max(lower)`: the last bound applied wins
+    pub fn classify(m_b: i64, upper: i64, z_m: i64, lower: i64) -> MlClamp {
+        let capped = m_b.min(upper);
+        if lower > capped.max(z_m) {
+            MlClamp::Lower
+        } else if z_m > capped {
+            MlClamp::Floor
+        } else if m_b > upper {
+            MlClamp::Upper
+        } else {
+            MlClamp::Free
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MlClamp::Free => "free",
+            MlClamp::Upper => "upper",
+            MlClamp::Floor => "z_m",
+            MlClamp::Lower => "lower",
+        }
+    }
+}
+
+/// Blocks governed by each constraint over a run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintSummary {
+    /// Indexed by `BlockLimit as usize`
+    pub block_limit: [u32; 5],
+    /// Indexed by `MlClamp as usize`
+    pub ml_clamp: [u32; 4],
+}
+
+impl ConstraintSummary {
+    pub fn record(&mut self, limit: BlockLimit, clamp: MlClamp) {
+        self.block_limit[limit as usize] += 1;
+        self.ml_clamp[clamp as usize] += 1;
+    }
+
+    pub fn blocks(&self, limit: BlockLimit) -> u32 {
+        self.block_limit[limit as usize]
+    }
+
+    pub fn clamped(&self, clamp: MlClamp) -> u32 {
+        self.ml_clamp[clamp as usize]
+    }
+}
@@ -0,0 +1,107 @@
+//! Elastic demand: broadcast volume responding to fee levels and confirmation delay
+
+use crate::lib_core::SimulationConfig;
+
+/// Demand response state carried from block to block.
+///
+/// The exogenous volume produced by `run_type` is treated as the demand users
+/// would have at the steady-state fee and an empty mempool. Each block that
+/// volume is scaled by
+///
+/// `(fee / fee_ref)^(-demand_elasticity) * (1 + delay)^(-delay_elasticity)`
+///
+/// where `fee` is a smoothed fee per byte paid in recent blocks and `delay` is
+/// a smoothed number of blocks needed to clear the mempool. Part of the volume
+/// priced out is deferred and returns once conditions improve.
+pub struct DemandResponse {
+    price_elasticity: f64,
+    delay_elasticity: f64,
+    defer_fraction: f64,
+    smoothing: f64,
+    reference_fee: f64,
+    fee_ema: f64,
+    delay_ema: f64,
+    deferred: f64,
+}
+
+impl DemandResponse {
+    pub fn new(config: &SimulationConfig) -> Self {
+        let reference_fee = Self::reference_fee(config);
+        DemandResponse {
+            price_elasticity: config.demand_elasticity,
+            delay_elasticity: config.delay_elasticity,
+            defer_fraction: config.demand_defer_fraction.clamp(0.0, 1.0),
+            smoothing: (config.reference_blocks(1.0) / config.demand_memory.max(1) as f64).min(1.0),
+            reference_fee,
+            fee_ema: reference_fee,
+            delay_ema: 0.0,
+            deferred: 0.0,
+        }
+    }
+
+    /// Minimum fee per byte at the steady-state long term median
+    fn reference_fee(config: &SimulationConfig) -> f64 {
+        let steady_state = config.steady_state.max(1) as f64;
+        config.r_base * config.t_r as f64 / (steady_state * steady_state)
+    }
+
+    /// Re-derive the reference fee after `r_base` or `t_r` changed mid-run.
+    /// The smoothed fee is rescaled with it, as fees paid so far would have
+    /// scaled under the new parameters, so demand keeps reacting to
+    /// congestion and not to the parameter change itself.
+    pub fn reprice(&mut self, config: &SimulationConfig) {
+        let reference_fee = Self::reference_fee(config);
+        if self.reference_fee > 0.0 {
+            self.fee_ema *= reference_fee / self.reference_fee;
+        } else {
+            self.fee_ema = reference_fee;
+        }
+        self.reference_fee = reference_fee;
+    }
+
+    /// Whether demand responds to fees or delay at all. When false the
+    /// simulation uses the exogenous volume unchanged.
+    pub fn is_active(&self) -> bool {
+        self.price_elasticity != 0.0 || self.delay_elasticity != 0.0
+    }
+
+    /// Current multiplier applied to exogenous demand
+    pub fn factor(&self) -> f64 {
+        let fee_ratio = (self.fee_ema / self.reference_fee).max(1e-12);
+        let price_term = fee_ratio.powf(-self.price_elasticity);
+        let delay_term = (1.0 + self.delay_ema).powf(-self.delay_elasticity);
+        (price_term * delay_term).clamp(0.0, 1e6)
+    }
+
+    /// Scale the exogenous volume (bytes) for this block.
+    ///
+    /// Priced-out volume is partly deferred; deferred volume returns at a rate
+    /// set by `demand_memory`, slowed down while the demand factor is below 1.
+    pub fn apply(&mut self, volume: i64) -> i64 {
+        let factor = self.factor();
+        let base = volume.max(0) as f64;
+        let wanted = base * factor;
+
+        if wanted < base {
+            self.deferred += (base - wanted) * self.defer_fraction;
+        }
+
+        let returning = self.deferred * self.smoothing * factor.min(1.0);
+        self.deferred -= returning;
+
+        (wanted + returning) as i64
+    }
+
+    /// Feed back the outcome of the block just built.
+    ///
+    /// `fee_per_byte` is the fee paid per included byte (None for empty
+    /// blocks, and in simple block mode, where blocks carry a penalty but no
+    /// fees) and `delay_blocks` the number of maximum-size blocks needed to
+    /// clear the remaining mempool.
+    pub fn observe(&mut self, fee_per_byte: Option<f64>, delay_blocks: f64) {
+        if let Some(fee) = fee_per_byte {
+            self.fee_ema += self.smoothing * (fee - self.fee_ema);
+        }
+        self.delay_ema += self.smoothing * (delay_blocks - self.delay_ema);
+    }
+}
@@ -0,0 +1,201 @@
+//! Analytic steady state for constant demand
+//!
+//! Under constant demand D the rules settle where blocks carry D and both
+//! medians have caught up: M_L = max(D, z_m), M_S = max(D, M_L), unless A_C
+//! (or the M_B_max terms) holds blocks below D, or the low-tier fee can't pay
+//! for a tx past the starting M_N, in which case the backlog grows without
+//! bound.
+//!
+//! The time to get there comes from a reduced model that steps the same
+//! median, penalty and fee equations as the engine, with the backlog kept as
+//! a tx count. Blocks grow until the marginal penalty of a tx meets the
+//! low-tier fee `f_r * t_sim`, about `B = M_N + (t_r * M_N^2 / M_L^2 - t_sim) / 2`,
+//! so each block costs O(1) apart from the median refreshes, which follow
+//! the engine's re-sort intervals.
+
+use crate::lib_core::{marginal_penalty, SimulationConfig};
+use crate::median::{median_of_unsorted, MedianWindow};
+
+/// M_B, M_S and M_L count as converged within this fraction of equilibrium
+pub const CONVERGENCE_TOLERANCE: f64 = 0.01;
+
+/// The reduced model gives up after this many long windows
+const HORIZON_WINDOWS: usize = 20;
+
+/// Fixed point of the rules for one demand level
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Equilibrium {
+    /// Constant demand in bytes per block
+    pub demand: i64,
+    pub m_b: i64,
+    pub m_l: i64,
+    pub m_s: i64,
+    pub m_n: i64,
+    pub m_b_max: i64,
+    /// Reference fee per byte at equilibrium
+    pub f_r: f64,
+    /// Bytes per block added to the backlog when blocks can't carry D
+    pub backlog_growth: i64,
+    /// Blocks from a flat `steady_state` start until M_B, M_S and M_L are
+    /// within `CONVERGENCE_TOLERANCE` of equilibrium and stay there for a
+    /// long window; None if that takes longer than the horizon
+    pub converge_blocks: Option<u32>,
+}
+
+impl Equilibrium {
+    /// Equilibrium for a constant `demand`. A_C is taken at block `config.n`.
+    pub fn solve(config: &SimulationConfig, demand: i64) -> Equilibrium {
+        let demand = demand.max(0);
+        let cap = config.sanity_schedule.cap(config, config.n as usize, demand.max(config.z_m));
+        let m_b = demand.min(config.rule_set.max_block_weight(config, demand, demand, cap));
+        // Blocks only outgrow M_N while the low-tier fee covers the first tx
+        // past it. If it doesn't at the starting medians they never grow.
+        let start = config.steady_state;
+        let grows = low_tier_fee(config, start) >= marginal_penalty(config.r_base, start, start, config.t_sim);
+        let m_b = if config.simple_blocks || grows { m_b } else { m_b.min(start) };
+        let m_l = m_b.max(config.z_m);
+        let m_s = m_b.max(m_l);
+        let m_n = config.rule_set.penalty_median(config, m_s, m_l);
+
+        let mut eq = Equilibrium {
+            demand,
+            m_b,
+            m_l,
+            m_s,
+            m_n,
+            m_b_max: config.rule_set.max_block_weight(config, m_n, m_l, cap),
+            f_r: config.r_base * config.t_r as f64 / (m_l as f64 * m_l as f64),
+            backlog_growth: demand - m_b,
+            converge_blocks: None,
+        };
+        eq.converge_blocks = converge_blocks(config, &eq);
+        eq
+    }
+
+    /// Equilibria over a range of demand levels
+    pub fn curve(config: &SimulationConfig, demands: &[i64]) -> Vec<Equilibrium> {
+        demands.iter().map(|&demand| Equilibrium::solve(config, demand)).collect()
+    }
+}
+
+/// Fee of one low-tier tx of `t_sim` bytes
+fn low_tier_fee(config: &SimulationConfig, m_l: i64) -> f64 {
+    config.r_base * config.t_r as f64 / (m_l as f64 * m_l as f64) * config.t_sim as f64
+}
+
+/// Largest block whose last low-tier tx still covers its marginal penalty
+fn fee_limited_weight(config: &SimulationConfig, m_n: i64, m_l: i64) -> i64 {
+    let m_n = m_n.max(1) as f64;
+    let m_l = m_l.max(1) as f64;
+    let excess = (config.t_r as f64 * m_n * m_n / (m_l * m_l) - config.t_sim as f64) / 2.0;
+    (m_n + excess.max(0.0)) as i64
+}
+
+/// A median window read the way the engine reads it: exact every block with
+/// `exact_median`, otherwise re-sorted every `interval` pushes
+enum ReducedWindow {
+    Exact(MedianWindow),
+    Periodic { ring: Vec<i64>, head: usize, interval: usize, median: i64 },
+}
+
+impl ReducedWindow {
+    fn new(len: usize, interval: usize, fill: i64) -> ReducedWindow {
+        if interval <= 1 {
+            ReducedWindow::Exact(MedianWindow::new(len, fill))
+        } else {
+            ReducedWindow::Periodic { ring: vec![fill; len.max(1)], head: 0, interval, median: fill }
+        }
+    }
+
+    /// Median seen by block `height`
+    fn median(&mut self, height: usize) -> i64 {
+        match self {
+            ReducedWindow::Exact(window) => window.median(),
+            ReducedWindow::Periodic { ring, head, interval, median } => {
+                if height.is_multiple_of(*interval) {
+                    let mut oldest_first = ring[*head..].to_vec();
+                    oldest_first.extend_from_slice(&ring[..*head]);
+                    *median = median_of_unsorted(&mut oldest_first);
+                }
+                *median
+            }
+        }
+    }
+
+    fn push(&mut self, value: i64) {
+        match self {
+            ReducedWindow::Exact(window) => window.push(value),
+            ReducedWindow::Periodic { ring, head, .. } => {
+                ring[*head] = value;
+                *head = (*head + 1) % ring.len();
+            }
+        }
+    }
+}
+
+/// Txs of `t_sim` bytes a block takes from a backlog of `queued`: it stops at
+/// M_B_max or at the first tx whose fee doesn't cover its marginal penalty
+fn block_txs(config: &SimulationConfig, queued: i64, m_n: i64, m_l: i64, m_b_max: i64) -> i64 {
+    let t_sim = config.t_sim.max(1);
+    let by_size = queued.min((m_b_max.max(0) + t_sim - 1) / t_sim);
+    if config.simple_blocks {
+        return by_size;
+    }
+    let fee = low_tier_fee(config, m_l);
+    let fits = |txs: i64| fee >= marginal_penalty(config.r_base, txs * t_sim, m_n, t_sim);
+    // Start from the closed form and step to the exact tx
+    let mut txs = (fee_limited_weight(config, m_n, m_l) / t_sim).min(by_size);
+    while txs > 0 && !fits(txs - 1) {
+        txs -= 1;
+    }
+    while txs < by_size && fits(txs) {
+        txs += 1;
+    }
+    txs
+}
+
+fn converge_blocks(config: &SimulationConfig, eq: &Equilibrium) -> Option<u32> {
+    let len_l = config.long_window_len();
+    let len_s = config.short_window_len();
+    let (interval_l, interval_s) = config.median_refresh_intervals();
+    let near = |value: i64, target: i64| {
+        (value - target).abs() as f64 <= CONVERGENCE_TOLERANCE * target.max(1) as f64
+    };
+
+    let t_sim = config.t_sim.max(1);
+    let mut long = ReducedWindow::new(len_l, interval_l, config.steady_state);
+    let mut short = ReducedWindow::new(len_s, interval_s, config.steady_state);
+    let mut m_b: i64 = 0;
+    let mut m_l_prev = config.steady_state;
+    let mut backlog: i64 = 0;
+    let mut entered: Option<usize> = None;
+
+    for i in 0..HORIZON_WINDOWS * len_l {
+        let m_l = long.median(i);
+        let m_s = short.median(i);
+
+        let ml_upper = (config.ml_mult * m_l_prev as f64) as i64;
+        let ml_lower = (m_l_prev as f64 / config.ml_mult) as i64;
+        long.push(m_b.min(ml_upper).max(config.z_m).max(ml_lower));
+        short.push(m_b.max(m_l));
+
+        let m_n = config.rule_set.penalty_median(config, m_s, m_l);
+        let sanity_cap = config.sanity_schedule.cap(config, i, m_l);
+        let m_b_max = config.rule_set.max_block_weight(config, m_n, m_l, sanity_cap);
+        backlog += eq.demand / t_sim;
+        let txs = block_txs(config, backlog, m_n, m_l, m_b_max);
+        m_b = if config.simple_blocks { m_b_max.min(backlog * t_sim) } else { txs * t_sim };
+        backlog -= txs;
+        m_l_prev = m_l;
+
+        if near(m_b, eq.m_b) && near(m_s, eq.m_s) && near(m_l, eq.m_l) {
+            let since = *entered.get_or_insert(i);
+            if i - since >= len_l {
+                return Some(since as u32);
+            }
+        } else {
+            entered = None;
+        }
+    }
+    None
+}
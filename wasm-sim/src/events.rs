@@ -0,0 +1,237 @@
+//! Scripted event timelines: demand shocks, attackers, parameter and rule
+//! changes applied at given block heights
+//!
+//! A scenario file has one event per line:
+//!
+//! ```text
+//! # height  event   arguments
+//! 5000      demand  10 720        # demand x10 for 720 blocks
+//! 6000      spam    2000000 1440  # attacker adds 2 MB/block at the high fee for 1440 blocks
+//! 8000      set     r_base 0.3    # change a config parameter
+//! 10000     rules   legacy reset  # hard fork; optional window transition (carry, reset, reclamp)
+//! ```
+
+use crate::fork::WindowTransition;
+use crate::lib_core::{RuleSet, SimulationConfig};
+
+/// Config parameters that can be set by name: by scripted events mid-run, or
+/// as factors of a sensitivity analysis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Param {
+    RBase,
+    TR,
+    ZM,
+    MlMult,
+    MnMult,
+    MlCapMult,
+    RampMultiplier,
+    RunType,
+    SanityStartWeight,
+    SanityStartBlock,
+    SanityGrowthRate,
+}
+
+impl Param {
+    pub const ALL: [Param; 11] = [
+        Param::RBase,
+        Param::TR,
+        Param::ZM,
+        Param::MlMult,
+        Param::MnMult,
+        Param::MlCapMult,
+        Param::RampMultiplier,
+        Param::RunType,
+        Param::SanityStartWeight,
+        Param::SanityStartBlock,
+        Param::SanityGrowthRate,
+    ];
+
+    pub fn parse(name: &str) -> Option<Param> {
+        Param::ALL.into_iter().find(|param| param.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Param::RBase => "r_base",
+            Param::TR => "t_r",
+            Param::ZM => "z_m",
+            Param::MlMult => "ml_mult",
+            Param::MnMult => "mn_mult",
+            Param::MlCapMult => "ml_cap_mult",
+            Param::RampMultiplier => "ramp_multiplier",
+            Param::RunType => "run_type",
+            Param::SanityStartWeight => "sanity_start_weight",
+            Param::SanityStartBlock => "sanity_start_block",
+            Param::SanityGrowthRate => "sanity_growth_rate",
+        }
+    }
+
+    /// Current value of the parameter in `config`
+    pub fn get(self, config: &SimulationConfig) -> f64 {
+        match self {
+            Param::RBase => config.r_base,
+            Param::TR => config.t_r as f64,
+            Param::ZM => config.z_m as f64,
+            Param::MlMult => config.ml_mult,
+            Param::MnMult => config.mn_mult,
+            Param::MlCapMult => config.ml_cap_mult,
+            Param::RampMultiplier => config.ramp_multiplier,
+            Param::RunType => config.run_type as f64,
+            Param::SanityStartWeight => config.sanity_start_weight as f64,
+            Param::SanityStartBlock => config.sanity_start_block as f64,
+            Param::SanityGrowthRate => config.sanity_growth_rate,
+        }
+    }
+
+    pub fn apply(self, config: &mut SimulationConfig, value: f64) {
+        match self {
+            Param::RBase => config.r_base = value,
+            Param::TR => config.t_r = value as i64,
+            Param::ZM => config.z_m = value as i64,
+            Param::MlMult => config.ml_mult = value,
+            Param::MnMult => config.mn_mult = value,
+            Param::MlCapMult => config.ml_cap_mult = value,
+            Param::RampMultiplier => config.ramp_multiplier = value,
+            Param::RunType => config.run_type = value as u32,
+            Param::SanityStartWeight => config.sanity_start_weight = value as i64,
+            Param::SanityStartBlock => config.sanity_start_block = value as u32,
+            Param::SanityGrowthRate => config.sanity_growth_rate = value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventAction {
+    /// Multiply exogenous demand by `factor` for `duration` blocks
+    Demand { factor: f64, duration: u32 },
+    /// An attacker broadcasts `bytes` per block at the high fee for `duration` blocks
+    Spam { bytes: i64, duration: u32 },
+    /// Change a config parameter from this height on
+    Set { param: Param, value: f64 },
+    /// Switch the consensus rule set from this height on. Without an explicit
+    /// window transition the config's `fork_windows` is used.
+    Rules { rules: RuleSet, windows: Option<WindowTransition> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledEvent {
+    pub height: u32,
+    pub action: EventAction,
+}
+
+/// Events ordered by height
+#[derive(Clone, Debug, Default)]
+pub struct EventTimeline {
+    pub events: Vec<ScheduledEvent>,
+}
+
+impl EventTimeline {
+    pub fn new(mut events: Vec<ScheduledEvent>) -> Self {
+        events.sort_by_key(|e| e.height);
+        EventTimeline { events }
+    }
+
+    /// Parse a scenario file (see the module docs for the format)
+    pub fn parse(text: &str) -> Result<EventTimeline, String> {
+        let mut events = Vec::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", line_no + 1, msg);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 2 {
+                return Err(err("expected height and event"));
+            }
+
+            let height: u32 = fields[0].parse().map_err(|_| err("invalid height"))?;
+            let arg = |k: usize| fields.get(k).copied().ok_or_else(|| err("missing argument"));
+            let number = |k: usize| -> Result<f64, String> {
+                arg(k)?.parse::<f64>().map_err(|_| err("invalid number"))
+            };
+
+            let action = match fields[1] {
+                "demand" => EventAction::Demand { factor: number(2)?, duration: number(3)? as u32 },
+                "spam" => EventAction::Spam { bytes: number(2)? as i64, duration: number(3)? as u32 },
+                "set" => {
+                    let param = Param::parse(arg(2)?).ok_or_else(|| err("unknown parameter"))?;
+                    EventAction::Set { param, value: number(3)? }
+                }
+                "rules" => EventAction::Rules {
+                    rules: RuleSet::parse(arg(2)?).ok_or_else(|| err("unknown rule set"))?,
+                    windows: match fields.get(3) {
+                        Some(name) => Some(WindowTransition::parse(name).ok_or_else(|| err("unknown window transition"))?),
+                        None => None,
+                    },
+                },
+                other => return Err(err(&format!("unknown event '{}'", other))),
+            };
+            events.push(ScheduledEvent { height, action });
+        }
+        Ok(EventTimeline::new(events))
+    }
+
+    pub fn load(path: &str) -> Result<EventTimeline, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        EventTimeline::parse(&text)
+    }
+}
+
+/// Walks a timeline during a run, applying due events and tracking the
+/// shocks currently in effect
+pub struct TimelineCursor<'a> {
+    events: &'a [ScheduledEvent],
+    next: usize,
+    /// (factor, last height) of active demand shocks
+    demand: Vec<(f64, u32)>,
+    /// (bytes, last height) of active attackers
+    spam: Vec<(i64, u32)>,
+}
+
+impl<'a> TimelineCursor<'a> {
+    pub fn new(timeline: Option<&'a EventTimeline>) -> Self {
+        TimelineCursor {
+            events: timeline.map_or(&[], |t| t.events.as_slice()),
+            next: 0,
+            demand: Vec::new(),
+            spam: Vec::new(),
+        }
+    }
+
+    /// Apply the events scheduled at or before `height`. Returns the window
+    /// transition to perform if the rule set was switched.
+    pub fn advance(&mut self, height: u32, config: &mut SimulationConfig) -> Option<WindowTransition> {
+        let mut switched = None;
+        while let Some(event) = self.events.get(self.next).filter(|e| e.height <= height) {
+            match event.action {
+                EventAction::Demand { factor, duration } => {
+                    self.demand.push((factor, event.height.saturating_add(duration.max(1) - 1)));
+                }
+                EventAction::Spam { bytes, duration } => {
+                    self.spam.push((bytes, event.height.saturating_add(duration.max(1) - 1)));
+                }
+                EventAction::Set { param, value } => param.apply(config, value),
+                EventAction::Rules { rules, windows } => {
+                    config.rule_set = rules;
+                    switched = Some(windows.unwrap_or(config.fork_windows));
+                }
+            }
+            self.next += 1;
+        }
+        self.demand.retain(|&(_, last)| last >= height);
+        self.spam.retain(|&(_, last)| last >= height);
+        switched
+    }
+
+    /// Product of the active demand shocks
+    pub fn demand_factor(&self) -> f64 {
+        self.demand.iter().map(|&(factor, _)| factor).product()
+    }
+
+    /// Attacker bytes broadcast this block
+    pub fn spam_bytes(&self) -> i64 {
+        self.spam.iter().map(|&(bytes, _)| bytes).sum()
+    }
+}
@@ -0,0 +1,117 @@
+//! Hard-fork transitions: switching rule sets mid-run and measuring the
+//! transient that follows activation
+
+use crate::lib_core::{RuleSet, SimulationConfig};
+use crate::median::median_of_sorted;
+
+/// What happens to the median windows when a fork activates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowTransition {
+    /// Keep the windows built under the old rules as they are
+    Carry,
+    /// Refill the long and short windows with the current M_L and M_S
+    Reset,
+    /// Re-clamp every long-term weight around the current M_L with the new
+    /// `ml_mult`/`z_m`, and raise short-term weights to at least M_L
+    Reclamp,
+}
+
+impl WindowTransition {
+    pub fn parse(name: &str) -> Option<WindowTransition> {
+        match name {
+            "carry" => Some(WindowTransition::Carry),
+            "reset" => Some(WindowTransition::Reset),
+            "reclamp" => Some(WindowTransition::Reclamp),
+            _ => None,
+        }
+    }
+
+    /// Numeric id used by the CLI and the WASM wrapper (0 = carry, 1 = reset, 2 = reclamp)
+    pub fn from_id(id: u32) -> WindowTransition {
+        match id {
+            1 => WindowTransition::Reset,
+            2 => WindowTransition::Reclamp,
+            _ => WindowTransition::Carry,
+        }
+    }
+
+    /// Rewrite the window contents in place. Returns the new M_L.
+    pub fn apply(self, config: &SimulationConfig, long: &mut [i64], short: &mut [i64]) -> i64 {
+        let m_l = current_median(long);
+        let m_s = current_median(short);
+
+        match self {
+            WindowTransition::Carry => {}
+            WindowTransition::Reset => {
+                long.fill(m_l);
+                short.fill(m_s);
+            }
+            WindowTransition::Reclamp => {
+                let upper = (config.ml_mult * m_l as f64) as i64;
+                let lower = (m_l as f64 / config.ml_mult) as i64;
+                for weight in long.iter_mut() {
+                    *weight = (*weight).min(upper).max(config.z_m).max(lower);
+                }
+                for weight in short.iter_mut() {
+                    *weight = (*weight).max(m_l);
+                }
+            }
+        }
+        current_median(long)
+    }
+}
+
+fn current_median(window: &[i64]) -> i64 {
+    let mut sorted = window.to_vec();
+    sorted.sort_unstable();
+    median_of_sorted(&sorted)
+}
+
+/// A rule switch that happened during a run and the transient after it
+#[derive(Clone, Copy, Debug)]
+pub struct ForkTransition {
+    pub height: u32,
+    pub from: RuleSet,
+    pub to: RuleSet,
+    pub windows: WindowTransition,
+    /// M_N of the last block under the old rules, or what they give for the
+    /// starting windows when the fork activates at block 0
+    pub m_n_before: i64,
+    /// M_B_max of the last block under the old rules (same caveat)
+    pub m_b_max_before: i64,
+    /// M_B_max of the first block under the new rules
+    pub m_b_max_after: i64,
+    /// Largest relative jump of M_N from its pre-fork value until the next
+    /// fork or the end of the run
+    pub peak_m_n_deviation: f64,
+    /// Blocks after activation until M_N stays within 2% of its value at the
+    /// end of the transient window; None if it never settles
+    pub settle_blocks: Option<u32>,
+}
+
+impl ForkTransition {
+    /// Fill in the transient metrics from the M_N series (indexed by block)
+    pub fn measure(&mut self, m_n: &[i64], end: usize) {
+        let start = self.height as usize;
+        let end = end.min(m_n.len());
+        if start >= end {
+            return;
+        }
+
+        let before = self.m_n_before.max(1) as f64;
+        self.peak_m_n_deviation = m_n[start..end]
+            .iter()
+            .map(|&v| (v as f64 - before).abs() / before)
+            .fold(0.0, f64::max);
+
+        let settled = m_n[end - 1].max(1) as f64;
+        let last_outside = m_n[start..end]
+            .iter()
+            .rposition(|&v| (v as f64 - settled).abs() / settled > 0.02);
+        self.settle_blocks = match last_outside {
+            None => Some(0),
+            Some(k) if start + k + 1 < end => Some(k as u32 + 1),
+            Some(_) => None,
+        };
+    }
+}
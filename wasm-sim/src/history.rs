@@ -0,0 +1,230 @@
+//! Historical chain data: replaying real block weights as demand and
+//! comparing simulated medians with the observed ones
+
+use crate::lib_core::SimulationResults;
+use crate::median::MedianWindow;
+
+/// One block of recorded chain history
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockRecord {
+    pub height: u64,
+    pub weight: i64,
+    pub tx_count: u32,
+    /// Weight the block added to the long term window, when recorded
+    pub long_term_weight: Option<i64>,
+}
+
+/// Median rules of a real chain, fixed by its consensus code rather than by
+/// the config under test
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainRules {
+    /// CRYPTONOTE_LONG_TERM_BLOCK_WEIGHT_WINDOW_SIZE
+    pub long_window: usize,
+    /// CRYPTONOTE_REWARD_BLOCKS_WINDOW
+    pub short_window: usize,
+    /// CRYPTONOTE_BLOCK_GRANTED_FULL_REWARD_ZONE_V5, the floor of both medians
+    pub full_reward_zone: i64,
+    /// CRYPTONOTE_SHORT_TERM_BLOCK_WEIGHT_SURGE_FACTOR, the cap on the short
+    /// term median as a multiple of M_L
+    pub surge_factor: i64,
+}
+
+/// Monero mainnet since the long term weight fork (v10)
+pub const MAINNET: ChainRules = ChainRules {
+    long_window: 100_000,
+    short_window: 100,
+    full_reward_zone: 300_000,
+    surge_factor: 50,
+};
+
+impl ChainRules {
+    /// Weight a block adds to the long term window: its weight capped at 1.4
+    /// times the long term median in effect when it was mined
+    pub fn long_term_weight(&self, weight: i64, m_l: i64) -> i64 {
+        weight.min(m_l + m_l * 2 / 5)
+    }
+}
+
+/// A contiguous run of recorded blocks, oldest first
+#[derive(Clone, Debug, Default)]
+pub struct ChainHistory {
+    pub blocks: Vec<BlockRecord>,
+}
+
+impl ChainHistory {
+    /// Parse history from CSV text.
+    ///
+    /// Each line is `height,block_weight[,tx_count[,long_term_weight]]`;
+    /// commas, tabs or spaces separate fields. Blank lines and `#` comments
+    /// are skipped, and so is a header: a first row where no field is a number. Block
+    /// headers dumped offline from monerod (`get_block_headers_range`) reduce
+    /// to this format by keeping `height`, `block_weight`, `num_txes` and
+    /// `long_term_weight`.
+    pub fn from_csv_str(text: &str) -> Result<ChainHistory, String> {
+        let mut blocks = Vec::new();
+        let mut first_row = true;
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line
+                .split([',', '\t', ' '])
+                .filter(|f| !f.is_empty())
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            if std::mem::take(&mut first_row) && fields.iter().all(|f| f.parse::<f64>().is_err()) {
+                continue; // header
+            }
+            if fields.len() < 2 {
+                return Err(format!("line {}: expected height and block weight", line_no + 1));
+            }
+
+            let height = fields[0]
+                .parse()
+                .map_err(|_| format!("line {}: invalid height '{}'", line_no + 1, fields[0]))?;
+            let weight = fields[1]
+                .parse()
+                .map_err(|_| format!("line {}: invalid block weight '{}'", line_no + 1, fields[1]))?;
+            let tx_count = match fields.get(2) {
+                Some(field) => field
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid tx count '{}'", line_no + 1, field))?,
+                None => 0,
+            };
+            let long_term_weight = match fields.get(3) {
+                Some(field) => Some(
+                    field
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid long term weight '{}'", line_no + 1, field))?,
+                ),
+                None => None,
+            };
+
+            blocks.push(BlockRecord { height, weight, tx_count, long_term_weight });
+        }
+
+        if blocks.is_empty() {
+            return Err("history contains no blocks".to_string());
+        }
+        Ok(ChainHistory { blocks })
+    }
+
+    /// Read and parse a history file
+    pub fn load(path: &str) -> Result<ChainHistory, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        ChainHistory::from_csv_str(&text)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Block weights in bytes, used as the broadcast volume per block
+    pub fn weights(&self) -> Vec<i64> {
+        self.blocks.iter().map(|b| b.weight).collect()
+    }
+
+    /// M_L and M_S of the chain under its own consensus `rules`: M_L is the
+    /// long term median of the blocks' long term weights (recomputed when the
+    /// file has none) and M_S the effective short term median of the raw
+    /// weights, both floored at the full reward zone, M_S capped at
+    /// `surge_factor * M_L`. The windows before the first recorded block are
+    /// unknown and start filled with its weights, so M_L is only exact once a
+    /// full long window of history has been replayed.
+    pub fn observed_medians(&self, rules: &ChainRules) -> ObservedSeries {
+        let mut observed = ObservedSeries::default();
+        let Some(first) = self.blocks.first() else {
+            return observed;
+        };
+        let mut long_window = MedianWindow::new(
+            rules.long_window,
+            first.long_term_weight.unwrap_or(first.weight).max(rules.full_reward_zone),
+        );
+        let mut short_window = MedianWindow::new(rules.short_window, first.weight);
+
+        for block in &self.blocks {
+            let m_l = long_window.median().max(rules.full_reward_zone);
+            let m_s = short_window.median().max(rules.full_reward_zone).min(rules.surge_factor * m_l);
+            observed.m_b.push(block.weight);
+            observed.m_l.push(m_l);
+            observed.m_s.push(m_s);
+
+            long_window.push(block.long_term_weight.unwrap_or_else(|| rules.long_term_weight(block.weight, m_l)));
+            short_window.push(block.weight);
+        }
+        observed
+    }
+
+    /// Compare a replay run against the medians mainnet had over the blocks
+    /// both cover
+    pub fn compare(&self, results: &SimulationResults) -> HistoryComparison {
+        let observed = self.observed_medians(&MAINNET);
+        HistoryComparison {
+            blocks: observed.m_b.len().min(results.M_B.len()),
+            m_b: SeriesError::between(&results.M_B, &observed.m_b),
+            m_l: SeriesError::between(&results.M_L, &observed.m_l),
+            m_s: SeriesError::between(&results.M_S, &observed.m_s),
+        }
+    }
+}
+
+/// M_B/M_L/M_S as observed on the recorded chain
+#[derive(Clone, Debug, Default)]
+pub struct ObservedSeries {
+    pub m_b: Vec<i64>,
+    pub m_l: Vec<i64>,
+    pub m_s: Vec<i64>,
+}
+
+/// Error of a simulated series against the observed one
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SeriesError {
+    pub rmse: f64,
+    pub mean_abs_pct: f64,
+    pub max_abs: i64,
+}
+
+impl SeriesError {
+    pub(crate) fn between(simulated: &[i64], observed: &[i64]) -> SeriesError {
+        let count = simulated.len().min(observed.len());
+        if count == 0 {
+            return SeriesError::default();
+        }
+
+        let mut sum_sq = 0.0;
+        let mut sum_pct = 0.0;
+        let mut max_abs: i64 = 0;
+        for (&sim, &obs) in simulated.iter().zip(observed.iter()) {
+            let diff = sim - obs;
+            sum_sq += (diff as f64) * (diff as f64);
+            if obs != 0 {
+                sum_pct += (diff as f64 / obs as f64).abs() * 100.0;
+            }
+            max_abs = max_abs.max(diff.abs());
+        }
+
+        SeriesError {
+            rmse: (sum_sq / count as f64).sqrt(),
+            mean_abs_pct: sum_pct / count as f64,
+            max_abs,
+        }
+    }
+}
+
+/// Summary of how closely a replay tracked the real chain
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HistoryComparison {
+    pub blocks: usize,
+    pub m_b: SeriesError,
+    pub m_l: SeriesError,
+    pub m_s: SeriesError,
+}
@@ -1,10 +1,36 @@
 //! Blockchain Dynamic Block Size Simulator
 //! Compiled to WebAssembly for high-performance browser execution
 
+pub mod batch;
+pub mod behavior;
+pub mod chart;
+pub mod compare;
+pub mod constraints;
+pub mod demand;
+pub mod equilibrium;
+pub mod events;
+pub mod fork;
+pub mod history;
 pub mod lib_core;
+pub mod lockstep;
+pub mod median;
+pub mod network;
+pub mod optimizer;
+pub mod resources;
+pub mod sanity;
+pub mod seed;
+pub mod sensitivity;
+pub mod summary;
+pub mod trace;
 
 use wasm_bindgen::prelude::*;
-use lib_core::{run_simulation_core, SimulationConfig as CoreConfig, SimulationResults as CoreResults};
+use behavior::FeeBehaviorModel;
+use equilibrium::Equilibrium;
+use fork::WindowTransition;
+use lib_core::{run_simulation_core, run_simulation_with_inputs, RuleSet, SimulationConfig as CoreConfig, SimulationInputs};
+use sanity::SanitySchedule;
+use seed::WindowSeed;
+use std::ptr::addr_of;
 
 // Global buffers for results (WASM memory)
 static mut RESULT_M_B: Vec<i64> = Vec::new();
@@ -15,7 +41,44 @@ static mut RESULT_INPUT_VOL: Vec<i64> = Vec::new();
 static mut RESULT_BLOCK_FEE: Vec<f64> = Vec::new();
 static mut RESULT_PENALTY: Vec<f64> = Vec::new();
 static mut RESULT_MEMPOOL: Vec<i64> = Vec::new();
+static mut RESULT_INPUT_VOL_HIGH: Vec<i64> = Vec::new();
+static mut RESULT_INPUT_VOL_LOW: Vec<i64> = Vec::new();
+static mut RESULT_MEMPOOL_HIGH: Vec<i64> = Vec::new();
+static mut RESULT_MEMPOOL_LOW: Vec<i64> = Vec::new();
 static mut RESULT_INDICES: Vec<u32> = Vec::new();
+static mut RESULT_TIMESTAMPS: Vec<f64> = Vec::new();
+static mut RESULT_SANITY_CAP: Vec<i64> = Vec::new();
+static mut RESULT_BLOCK_LIMIT: Vec<u8> = Vec::new();
+static mut RESULT_ML_CLAMP: Vec<u8> = Vec::new();
+
+// Full trace series (empty unless `full_trace` is set)
+static mut TRACE_M_L_WEIGHT: Vec<i64> = Vec::new();
+static mut TRACE_M_S_WEIGHT: Vec<i64> = Vec::new();
+static mut TRACE_M_B_MAX: Vec<i64> = Vec::new();
+static mut TRACE_F_R: Vec<f64> = Vec::new();
+static mut TRACE_LAST_F_T: Vec<f64> = Vec::new();
+static mut TRACE_MEMPOOL_HIGH: Vec<i64> = Vec::new();
+static mut TRACE_MEMPOOL_LOW: Vec<i64> = Vec::new();
+static mut TRACE_BLOCKFILLED_TIER: Vec<i64> = Vec::new();
+static mut TRACE_BLOCKFILLED_COUNT: Vec<i64> = Vec::new();
+static mut TRACE_PERCENT_RESPONSE: Vec<f64> = Vec::new();
+
+// Run B of the last comparison (run A uses the buffers above)
+static mut COMPARE_B_M_B: Vec<i64> = Vec::new();
+static mut COMPARE_B_M_L: Vec<i64> = Vec::new();
+static mut COMPARE_B_M_S: Vec<i64> = Vec::new();
+static mut COMPARE_B_M_N: Vec<i64> = Vec::new();
+static mut COMPARE_B_BLOCK_FEE: Vec<f64> = Vec::new();
+static mut COMPARE_B_PENALTY: Vec<f64> = Vec::new();
+static mut COMPARE_B_MEMPOOL: Vec<i64> = Vec::new();
+static mut COMPARE_M_B_DELTA: Vec<i64> = Vec::new();
+static mut COMPARE_THRESHOLDS: Vec<i64> = Vec::new();
+
+// Windows the last run ended with, and the seed later runs start from
+static mut FINAL_LONG_WINDOW: Vec<i64> = Vec::new();
+static mut FINAL_SHORT_WINDOW: Vec<i64> = Vec::new();
+static mut FINAL_LAST_BLOCK_WEIGHT: i64 = 0;
+static mut WINDOW_SEED: Option<WindowSeed> = None;
 
 // Stats
 static mut STATS_MAX_MB: i64 = 0;
@@ -49,11 +112,48 @@ pub struct SimulationConfig {
     pub use_long_term_median_cap: bool,
     pub sanity_start_weight: i64,
     pub sanity_start_block: u32,
+    pub demand_elasticity: f64,
+    pub delay_elasticity: f64,
+    pub demand_defer_fraction: f64,
+    pub demand_memory: u32,
+    /// Fee behavior model kind and parameters, see
+    /// `FeeBehaviorModel::from_parts` (negative = model default)
+    pub fee_behavior: u32,
+    pub behavior_param_a: f64,
+    pub behavior_param_b: f64,
+    pub block_time: u32,
+    pub long_window: u32,
+    pub short_window: u32,
+    pub miners: u32,
+    pub largest_miner_share: f64,
+    pub miner_bandwidth: f64,
+    pub propagation_latency: f64,
+    pub validation_rate: f64,
+    pub stochastic_intervals: bool,
+    /// 0 = current rules, 1 = legacy rules
+    pub rule_set: u32,
+    /// Hard fork height (0 = none), target rule set and window transition
+    /// (0 = carry, 1 = reset, 2 = reclamp)
+    pub fork_height: u32,
+    pub fork_rule_set: u32,
+    pub fork_windows: u32,
+    /// A_C shape (0 = compound, 1 = linear, 2 = stepwise per year, 3 = tied
+    /// to M_L), growth per 2-minute block and the M_L multiple for shape 3
+    pub sanity_schedule: u32,
+    pub sanity_growth_rate: f64,
+    pub sanity_ml_multiple: f64,
+    /// Record the intermediate series read through the `get_trace_*` pointers
+    pub full_trace: bool,
+    /// Multiple of M_L in M_B_max (16 by default)
+    pub ml_cap_mult: f64,
+    /// Varies the random streams (0 = fixed seeds)
+    pub seed: u64,
 }
 
 #[wasm_bindgen]
 impl SimulationConfig {
     #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         n: u32,
         steady_state: i64,
@@ -78,6 +178,9 @@ impl SimulationConfig {
         sanity_start_weight: i64,
         sanity_start_block: u32,
     ) -> SimulationConfig {
+        // Options added after the constructor signature was fixed keep their
+        // core defaults and are set from JS through the public fields.
+        let defaults = CoreConfig::default();
         SimulationConfig {
             n,
             steady_state,
@@ -101,7 +204,126 @@ impl SimulationConfig {
             use_long_term_median_cap,
             sanity_start_weight,
             sanity_start_block,
+            demand_elasticity: defaults.demand_elasticity,
+            delay_elasticity: defaults.delay_elasticity,
+            demand_defer_fraction: defaults.demand_defer_fraction,
+            demand_memory: defaults.demand_memory,
+            fee_behavior: 0,
+            behavior_param_a: -1.0,
+            behavior_param_b: -1.0,
+            block_time: defaults.block_time,
+            long_window: defaults.long_window,
+            short_window: defaults.short_window,
+            miners: defaults.miners,
+            largest_miner_share: defaults.largest_miner_share,
+            miner_bandwidth: defaults.miner_bandwidth,
+            propagation_latency: defaults.propagation_latency,
+            validation_rate: defaults.validation_rate,
+            stochastic_intervals: defaults.stochastic_intervals,
+            rule_set: 0,
+            fork_height: defaults.fork_height,
+            fork_rule_set: 0,
+            fork_windows: 0,
+            sanity_schedule: 0,
+            sanity_growth_rate: defaults.sanity_growth_rate,
+            sanity_ml_multiple: 16.0,
+            full_trace: defaults.full_trace,
+            ml_cap_mult: defaults.ml_cap_mult,
+            seed: defaults.seed,
+        }
+    }
+}
+
+/// Layout version leading serialized configs
+const BUFFER_VERSION: f64 = 1.0;
+
+/// Layout version leading result buffers
+const RESULT_BUFFER_VERSION: f64 = 2.0;
+
+/// A config field stored in a serialized config: numbers as f64, bools as
+/// 0/1, u64 as its high and low 32 bits so seeds survive the trip
+trait BufferField: Sized {
+    fn write(self, out: &mut Vec<f64>);
+    fn read(values: &mut impl Iterator<Item = f64>) -> Option<Self>;
+}
+
+macro_rules! numeric_buffer_field {
+    ($($t:ty),*) => {$(
+        impl BufferField for $t {
+            fn write(self, out: &mut Vec<f64>) {
+                out.push(self as f64);
+            }
+            fn read(values: &mut impl Iterator<Item = f64>) -> Option<Self> {
+                values.next().map(|v| v as $t)
+            }
+        }
+    )*};
+}
+numeric_buffer_field!(u32, i64, f64);
+
+impl BufferField for bool {
+    fn write(self, out: &mut Vec<f64>) {
+        out.push(if self { 1.0 } else { 0.0 });
+    }
+    fn read(values: &mut impl Iterator<Item = f64>) -> Option<Self> {
+        values.next().map(|v| v != 0.0)
+    }
+}
+
+impl BufferField for u64 {
+    fn write(self, out: &mut Vec<f64>) {
+        out.push((self >> 32) as f64);
+        out.push((self & 0xffff_ffff) as f64);
+    }
+    fn read(values: &mut impl Iterator<Item = f64>) -> Option<Self> {
+        Some(((values.next()? as u64) << 32) | values.next()? as u64)
+    }
+}
+
+/// Serialization in field order; the struct literal in `from_buffer` fails
+/// to compile if a field is added to `SimulationConfig` but not listed here
+macro_rules! config_buffer {
+    ($($field:ident),* $(,)?) => {
+        impl SimulationConfig {
+            fn to_buffer(self) -> Vec<f64> {
+                let mut out = vec![BUFFER_VERSION];
+                $(self.$field.write(&mut out);)*
+                out
+            }
+
+            fn from_buffer(data: &[f64]) -> Option<SimulationConfig> {
+                let mut values = data.iter().copied();
+                if values.next()? != BUFFER_VERSION {
+                    return None;
+                }
+                let config = SimulationConfig { $($field: BufferField::read(&mut values)?),* };
+                values.next().is_none().then_some(config)
+            }
         }
+    };
+}
+
+config_buffer!(
+    n, steady_state, z_m, t_r, r_base, mid_100k, mid_100, t_sim, run_type, ramp_multiplier, ml_mult, mn_mult,
+    add_noise, users_pay_more, simple_blocks, large_sim_mode, exact_median, max_blocksize,
+    max_blocksize_growth_rate, use_long_term_median_cap, sanity_start_weight, sanity_start_block,
+    demand_elasticity, delay_elasticity, demand_defer_fraction, demand_memory, fee_behavior, behavior_param_a,
+    behavior_param_b, block_time, long_window, short_window, miners, largest_miner_share, miner_bandwidth,
+    propagation_latency, validation_rate, stochastic_intervals, rule_set, fork_height, fork_rule_set,
+    fork_windows, sanity_schedule, sanity_growth_rate, sanity_ml_multiple, full_trace, ml_cap_mult, seed,
+);
+
+#[wasm_bindgen]
+impl SimulationConfig {
+    /// The config as a flat Float64Array that can be posted to a worker and
+    /// passed to `run_simulation_buffer`
+    pub fn serialize(&self) -> Vec<f64> {
+        self.to_buffer()
+    }
+
+    /// Inverse of `serialize`; undefined for a buffer of another layout
+    pub fn deserialize(data: &[f64]) -> Option<SimulationConfig> {
+        SimulationConfig::from_buffer(data)
     }
 }
 
@@ -120,6 +342,7 @@ impl From<SimulationConfig> for CoreConfig {
             ramp_multiplier: config.ramp_multiplier,
             ml_mult: config.ml_mult,
             mn_mult: config.mn_mult,
+            ml_cap_mult: config.ml_cap_mult,
             add_noise: config.add_noise,
             users_pay_more: config.users_pay_more,
             simple_blocks: config.simple_blocks,
@@ -130,6 +353,32 @@ impl From<SimulationConfig> for CoreConfig {
             use_long_term_median_cap: config.use_long_term_median_cap,
             sanity_start_weight: config.sanity_start_weight,
             sanity_start_block: config.sanity_start_block,
+            demand_elasticity: config.demand_elasticity,
+            delay_elasticity: config.delay_elasticity,
+            demand_defer_fraction: config.demand_defer_fraction,
+            demand_memory: config.demand_memory,
+            fee_behavior: FeeBehaviorModel::from_parts(
+                config.fee_behavior,
+                config.behavior_param_a,
+                config.behavior_param_b,
+            ),
+            block_time: config.block_time,
+            long_window: config.long_window,
+            short_window: config.short_window,
+            miners: config.miners,
+            largest_miner_share: config.largest_miner_share,
+            miner_bandwidth: config.miner_bandwidth,
+            propagation_latency: config.propagation_latency,
+            validation_rate: config.validation_rate,
+            stochastic_intervals: config.stochastic_intervals,
+            rule_set: RuleSet::from_id(config.rule_set),
+            fork_height: config.fork_height,
+            fork_rule_set: RuleSet::from_id(config.fork_rule_set),
+            fork_windows: WindowTransition::from_id(config.fork_windows),
+            sanity_schedule: SanitySchedule::from_id(config.sanity_schedule, config.sanity_ml_multiple),
+            sanity_growth_rate: config.sanity_growth_rate,
+            full_trace: config.full_trace,
+            seed: config.seed,
         }
     }
 }
@@ -147,52 +396,198 @@ pub struct SimulationResults {
 /// Get pointer to result arrays for JS to read
 #[wasm_bindgen]
 pub fn get_m_b_ptr() -> *const i64 {
-    unsafe { RESULT_M_B.as_ptr() }
+    unsafe { (*addr_of!(RESULT_M_B)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_m_l_ptr() -> *const i64 {
-    unsafe { RESULT_M_L.as_ptr() }
+    unsafe { (*addr_of!(RESULT_M_L)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_m_s_ptr() -> *const i64 {
-    unsafe { RESULT_M_S.as_ptr() }
+    unsafe { (*addr_of!(RESULT_M_S)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_m_n_ptr() -> *const i64 {
-    unsafe { RESULT_M_N.as_ptr() }
+    unsafe { (*addr_of!(RESULT_M_N)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_input_vol_ptr() -> *const i64 {
-    unsafe { RESULT_INPUT_VOL.as_ptr() }
+    unsafe { (*addr_of!(RESULT_INPUT_VOL)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_block_fee_ptr() -> *const f64 {
-    unsafe { RESULT_BLOCK_FEE.as_ptr() }
+    unsafe { (*addr_of!(RESULT_BLOCK_FEE)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_penalty_ptr() -> *const f64 {
-    unsafe { RESULT_PENALTY.as_ptr() }
+    unsafe { (*addr_of!(RESULT_PENALTY)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_mempool_ptr() -> *const i64 {
-    unsafe { RESULT_MEMPOOL.as_ptr() }
+    unsafe { (*addr_of!(RESULT_MEMPOOL)).as_ptr() }
+}
+
+/// Per-tier broadcast and mempool bytes (high = 16*f_r tier, low = f_r tier)
+#[wasm_bindgen]
+pub fn get_input_vol_high_ptr() -> *const i64 {
+    unsafe { (*addr_of!(RESULT_INPUT_VOL_HIGH)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_input_vol_low_ptr() -> *const i64 {
+    unsafe { (*addr_of!(RESULT_INPUT_VOL_LOW)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_mempool_high_ptr() -> *const i64 {
+    unsafe { (*addr_of!(RESULT_MEMPOOL_HIGH)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_mempool_low_ptr() -> *const i64 {
+    unsafe { (*addr_of!(RESULT_MEMPOOL_LOW)).as_ptr() }
 }
 
 #[wasm_bindgen]
 pub fn get_indices_ptr() -> *const u32 {
-    unsafe { RESULT_INDICES.as_ptr() }
+    unsafe { (*addr_of!(RESULT_INDICES)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_timestamps_ptr() -> *const f64 {
+    unsafe { (*addr_of!(RESULT_TIMESTAMPS)).as_ptr() }
+}
+
+/// A_C per block; i64::MAX where no cap applies
+#[wasm_bindgen]
+pub fn get_sanity_cap_ptr() -> *const i64 {
+    unsafe { (*addr_of!(RESULT_SANITY_CAP)).as_ptr() }
+}
+
+/// What limited each block: 0 = 2*M_N, 1 = M_L cap (ml_cap_mult*M_L), 2 = A_C, 3 = fee, 4 = demand
+#[wasm_bindgen]
+pub fn get_block_limit_ptr() -> *const u8 {
+    unsafe { (*addr_of!(RESULT_BLOCK_LIMIT)).as_ptr() }
+}
+
+/// M_L weight clamp per block: 0 = none, 1 = upper, 2 = z_m floor, 3 = lower
+#[wasm_bindgen]
+pub fn get_ml_clamp_ptr() -> *const u8 {
+    unsafe { (*addr_of!(RESULT_ML_CLAMP)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_m_l_weight_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_M_L_WEIGHT)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_m_s_weight_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_M_S_WEIGHT)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_m_b_max_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_M_B_MAX)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_f_r_ptr() -> *const f64 {
+    unsafe { (*addr_of!(TRACE_F_R)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_last_f_t_ptr() -> *const f64 {
+    unsafe { (*addr_of!(TRACE_LAST_F_T)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_mempool_high_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_MEMPOOL_HIGH)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_mempool_low_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_MEMPOOL_LOW)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_blockfilled_tier_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_BLOCKFILLED_TIER)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_blockfilled_count_ptr() -> *const i64 {
+    unsafe { (*addr_of!(TRACE_BLOCKFILLED_COUNT)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_trace_percent_response_ptr() -> *const f64 {
+    unsafe { (*addr_of!(TRACE_PERCENT_RESPONSE)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_m_b_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_B_M_B)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_m_l_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_B_M_L)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_m_s_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_B_M_S)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_m_n_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_B_M_N)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_block_fee_ptr() -> *const f64 {
+    unsafe { (*addr_of!(COMPARE_B_BLOCK_FEE)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_penalty_ptr() -> *const f64 {
+    unsafe { (*addr_of!(COMPARE_B_PENALTY)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_b_mempool_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_B_MEMPOOL)).as_ptr() }
+}
+
+/// M_B of run B minus M_B of run A, per block
+#[wasm_bindgen]
+pub fn get_compare_m_b_delta_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_M_B_DELTA)).as_ptr() }
+}
+
+/// (threshold, first height in A, first height in B) triples; -1 if never reached
+#[wasm_bindgen]
+pub fn get_compare_thresholds_ptr() -> *const i64 {
+    unsafe { (*addr_of!(COMPARE_THRESHOLDS)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_compare_thresholds_len() -> u32 {
+    unsafe { (*addr_of!(COMPARE_THRESHOLDS)).len() as u32 }
 }
 
 #[wasm_bindgen]
 pub fn get_result_len() -> u32 {
-    unsafe { RESULT_M_B.len() as u32 }
+    unsafe { (*addr_of!(RESULT_M_B)).len() as u32 }
 }
 
 #[wasm_bindgen]
@@ -215,14 +610,220 @@ pub fn get_cumulative_fees() -> f64 {
     unsafe { STATS_CUMULATIVE_FEES }
 }
 
+/// Inputs of runs started from JS: the window seed, if one is set
+fn page_inputs() -> SimulationInputs {
+    SimulationInputs {
+        window_seed: unsafe { (*addr_of!(WINDOW_SEED)).clone() },
+        ..SimulationInputs::default()
+    }
+}
+
 /// Main simulation function - NOTE: This returns immediately but data must be read from memory
 #[wasm_bindgen]
 pub fn run_simulation(config: SimulationConfig) -> SimulationResults {
-    // Run the optimized core simulation
+    // Run the optimized core simulation, from the window seed if one is set
     let core_config: CoreConfig = config.into();
-    let core_results = run_simulation_core(core_config);
+    let core_results = run_simulation_with_inputs(core_config, &page_inputs());
     
-    // Store results in global statics
+    let results = SimulationResults {
+        max_mb: core_results.max_mb,
+        max_penalty: core_results.max_penalty,
+        max_mempool: core_results.max_mempool,
+        cumulative_fees: core_results.cumulative_fees,
+        data_points: core_results.data_points,
+    };
+    store_results(core_results);
+    
+    // NOTE: For now, we're not storing per-block data to avoid the performance overhead
+    // The web UI will need to be updated to only show summary stats
+    // Or we need to run the simulation again to collect detailed data
+    
+    results
+}
+
+/// Start the median windows of later `run_simulation` and `run_comparison`
+/// calls from these weights (oldest first) instead of `steady_state`
+#[wasm_bindgen]
+pub fn set_window_seed(long_weights: Vec<i64>, short_weights: Vec<i64>, last_block_weight: i64) {
+    unsafe {
+        WINDOW_SEED = Some(WindowSeed { long_weights, short_weights, last_block_weight });
+    }
+}
+
+/// Start later runs where the last run left off, chaining runs
+#[wasm_bindgen]
+pub fn seed_from_last_run() {
+    unsafe {
+        WINDOW_SEED = Some(WindowSeed {
+            long_weights: (*addr_of!(FINAL_LONG_WINDOW)).clone(),
+            short_weights: (*addr_of!(FINAL_SHORT_WINDOW)).clone(),
+            last_block_weight: FINAL_LAST_BLOCK_WEIGHT,
+        });
+    }
+}
+
+/// Start later runs from windows filled with `steady_state` again
+#[wasm_bindgen]
+pub fn clear_window_seed() {
+    unsafe {
+        WINDOW_SEED = None;
+    }
+}
+
+/// Long window the last run ended with, oldest first
+#[wasm_bindgen]
+pub fn get_final_long_window_ptr() -> *const i64 {
+    unsafe { (*addr_of!(FINAL_LONG_WINDOW)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_final_long_window_len() -> u32 {
+    unsafe { (*addr_of!(FINAL_LONG_WINDOW)).len() as u32 }
+}
+
+/// Short window the last run ended with, oldest first
+#[wasm_bindgen]
+pub fn get_final_short_window_ptr() -> *const i64 {
+    unsafe { (*addr_of!(FINAL_SHORT_WINDOW)).as_ptr() }
+}
+
+#[wasm_bindgen]
+pub fn get_final_short_window_len() -> u32 {
+    unsafe { (*addr_of!(FINAL_SHORT_WINDOW)).len() as u32 }
+}
+
+#[wasm_bindgen]
+pub fn get_final_last_block_weight() -> i64 {
+    unsafe { FINAL_LAST_BLOCK_WEIGHT }
+}
+
+/// Header fields leading a result buffer: layout version, points, max_mb,
+/// max_penalty, max_mempool, cumulative_fees, and the `SERIES_*` flags of the
+/// optional series groups it holds
+pub const RESULT_HEADER_LEN: u32 = 7;
+
+/// Series following the header of a result buffer, `points` values each
+pub const RESULT_SERIES: [&str; 10] = [
+    "M_B",
+    "M_L",
+    "M_S",
+    "M_N",
+    "input_volume",
+    "block_fee",
+    "penalty",
+    "mempool_size",
+    "indices",
+    "timestamps",
+];
+
+/// Optional series groups of a result buffer, appended after `RESULT_SERIES`
+/// in flag order
+pub const SERIES_TIERS: u32 = 1;
+pub const SERIES_SANITY_CAP: u32 = 2;
+pub const SERIES_CONSTRAINTS: u32 = 4;
+/// Only present when the config enables `full_trace`
+pub const SERIES_TRACE: u32 = 8;
+
+/// Each optional group's flag and series names
+const RESULT_GROUPS: [(u32, &[&str]); 4] = [
+    (SERIES_TIERS, &["input_volume_high", "input_volume_low", "mempool_high", "mempool_low"]),
+    (SERIES_SANITY_CAP, &["sanity_cap"]),
+    (SERIES_CONSTRAINTS, &["block_limit", "ml_clamp"]),
+    (
+        SERIES_TRACE,
+        &[
+            "m_l_weight",
+            "m_s_weight",
+            "m_b_max",
+            "f_r",
+            "last_f_t",
+            "trace_mempool_high",
+            "trace_mempool_low",
+            "blockfilled_tier",
+            "blockfilled_count",
+            "percent_response",
+        ],
+    ),
+];
+
+/// Worker-friendly run: takes a config from `SimulationConfig::serialize`
+/// and returns a Float64Array owning the whole result (header, the
+/// `RESULT_SERIES`, then the optional groups asked for in `series`), so the
+/// worker can transfer its buffer to the page. The header's flags say which
+/// groups the buffer holds. Touches none of the global result buffers, so
+/// runs in separate workers (or instances) never see each other's data.
+/// Returns an empty array if the config buffer has another layout.
+#[wasm_bindgen]
+pub fn run_simulation_buffer(config: &[f64], series: u32) -> Vec<f64> {
+    let Some(config) = SimulationConfig::from_buffer(config) else {
+        return Vec::new();
+    };
+    let results = run_simulation_core(config.into());
+    let points = results.M_B.len();
+    let trace = results.trace.as_ref();
+    let flags = if trace.is_some() { series } else { series & !SERIES_TRACE };
+
+    let mut out = Vec::with_capacity(RESULT_HEADER_LEN as usize + get_result_buffer_series(flags).len() * points);
+    out.extend([
+        RESULT_BUFFER_VERSION,
+        points as f64,
+        results.max_mb as f64,
+        results.max_penalty,
+        results.max_mempool as f64,
+        results.cumulative_fees,
+        flags as f64,
+    ]);
+    for series in [&results.M_B, &results.M_L, &results.M_S, &results.M_N, &results.input_volume] {
+        out.extend(series.iter().map(|&v| v as f64));
+    }
+    out.extend(&results.block_fee);
+    out.extend(&results.penalty);
+    out.extend(results.mempool_size.iter().map(|&v| v as f64));
+    out.extend(results.indices.iter().map(|&v| v as f64));
+    out.extend(&results.timestamps);
+
+    if flags & SERIES_TIERS != 0 {
+        for series in [&results.input_volume_high, &results.input_volume_low, &results.mempool_high, &results.mempool_low] {
+            out.extend(series.iter().map(|&v| v as f64));
+        }
+    }
+    if flags & SERIES_SANITY_CAP != 0 {
+        out.extend(results.sanity_cap.iter().map(|&v| v as f64));
+    }
+    if flags & SERIES_CONSTRAINTS != 0 {
+        for series in [&results.block_limit, &results.ml_clamp] {
+            out.extend(series.iter().map(|&v| v as f64));
+        }
+    }
+    if let Some(trace) = trace.filter(|_| flags & SERIES_TRACE != 0) {
+        for series in [&trace.m_l_weight, &trace.m_s_weight, &trace.m_b_max] {
+            out.extend(series.iter().map(|&v| v as f64));
+        }
+        out.extend(&trace.f_r);
+        out.extend(&trace.last_f_t);
+        for series in [&trace.mempool_high, &trace.mempool_low, &trace.blockfilled_tier, &trace.blockfilled_count] {
+            out.extend(series.iter().map(|&v| v as f64));
+        }
+        out.extend(&trace.percent_response);
+    }
+    out
+}
+
+/// Names of the series in a result buffer with the given group flags, in order
+#[wasm_bindgen]
+pub fn get_result_buffer_series(flags: u32) -> Vec<String> {
+    let optional = RESULT_GROUPS.iter().filter(|(flag, _)| flags & flag != 0).flat_map(|(_, names)| names.iter());
+    RESULT_SERIES.iter().chain(optional).map(|name| name.to_string()).collect()
+}
+
+/// Number of header fields before the first series of a result buffer
+#[wasm_bindgen]
+pub fn get_result_buffer_header_len() -> u32 {
+    RESULT_HEADER_LEN
+}
+
+/// Store a run's results in the global statics read through the pointer exports
+fn store_results(core_results: lib_core::SimulationResults) {
     unsafe {
         STATS_MAX_MB = core_results.max_mb;
         STATS_MAX_PENALTY = core_results.max_penalty;
@@ -238,19 +839,118 @@ pub fn run_simulation(config: SimulationConfig) -> SimulationResults {
         RESULT_BLOCK_FEE = core_results.block_fee;
         RESULT_PENALTY = core_results.penalty;
         RESULT_MEMPOOL = core_results.mempool_size;
+        RESULT_INPUT_VOL_HIGH = core_results.input_volume_high;
+        RESULT_INPUT_VOL_LOW = core_results.input_volume_low;
+        RESULT_MEMPOOL_HIGH = core_results.mempool_high;
+        RESULT_MEMPOOL_LOW = core_results.mempool_low;
         RESULT_INDICES = core_results.indices;
+        RESULT_TIMESTAMPS = core_results.timestamps;
+        RESULT_SANITY_CAP = core_results.sanity_cap;
+        RESULT_BLOCK_LIMIT = core_results.block_limit;
+        RESULT_ML_CLAMP = core_results.ml_clamp;
+        FINAL_LONG_WINDOW = core_results.final_windows.long_weights;
+        FINAL_SHORT_WINDOW = core_results.final_windows.short_weights;
+        FINAL_LAST_BLOCK_WEIGHT = core_results.final_windows.last_block_weight;
+        
+        let trace = core_results.trace.unwrap_or_default();
+        TRACE_M_L_WEIGHT = trace.m_l_weight;
+        TRACE_M_S_WEIGHT = trace.m_s_weight;
+        TRACE_M_B_MAX = trace.m_b_max;
+        TRACE_F_R = trace.f_r;
+        TRACE_LAST_F_T = trace.last_f_t;
+        TRACE_MEMPOOL_HIGH = trace.mempool_high;
+        TRACE_MEMPOOL_LOW = trace.mempool_low;
+        TRACE_BLOCKFILLED_TIER = trace.blockfilled_tier;
+        TRACE_BLOCKFILLED_COUNT = trace.blockfilled_count;
+        TRACE_PERCENT_RESPONSE = trace.percent_response;
     }
+}
+
+/// A/B comparison summary (WASM wrapper)
+#[wasm_bindgen]
+pub struct ComparisonResults {
+    pub blocks: u32,
+    /// Largest |M_B(B) - M_B(A)| and the height it occurred at
+    pub max_mb_divergence: i64,
+    pub max_mb_divergence_at: u32,
+    pub max_mn_divergence: i64,
+    pub max_mn_divergence_at: u32,
+    pub fees_a: f64,
+    pub fees_b: f64,
+    pub penalty_a: f64,
+    pub penalty_b: f64,
+}
+
+/// Run configs A and B on the same inputs, from the window seed if one is
+/// set. A's series go to the usual result buffers, B's to the
+/// `get_compare_b_*` buffers; thresholds are read through
+/// `get_compare_thresholds_ptr`.
+#[wasm_bindgen]
+pub fn run_comparison(config_a: SimulationConfig, config_b: SimulationConfig) -> ComparisonResults {
+    let config_a: CoreConfig = config_a.into();
+    let thresholds = [2, 5, 10].map(|k| k * config_a.steady_state);
+    let comparison = compare::compare_with_inputs(config_a, config_b.into(), &page_inputs(), &thresholds);
+    let summary = ComparisonResults {
+        blocks: comparison.blocks as u32,
+        max_mb_divergence: comparison.m_b.error.max_abs,
+        max_mb_divergence_at: comparison.m_b.max_at,
+        max_mn_divergence: comparison.m_n.error.max_abs,
+        max_mn_divergence_at: comparison.m_n.max_at,
+        fees_a: comparison.fees_total()[0],
+        fees_b: comparison.fees_total()[1],
+        penalty_a: comparison.penalty_total[0],
+        penalty_b: comparison.penalty_total[1],
+    };
     
-    // NOTE: For now, we're not storing per-block data to avoid the performance overhead
-    // The web UI will need to be updated to only show summary stats
-    // Or we need to run the simulation again to collect detailed data
-    
-    SimulationResults {
-        max_mb: core_results.max_mb,
-        max_penalty: core_results.max_penalty,
-        max_mempool: core_results.max_mempool,
-        cumulative_fees: core_results.cumulative_fees,
-        data_points: core_results.data_points,
+    unsafe {
+        COMPARE_THRESHOLDS = comparison
+            .thresholds
+            .iter()
+            .flat_map(|t| [t.threshold, t.a.map_or(-1, i64::from), t.b.map_or(-1, i64::from)])
+            .collect();
+        COMPARE_M_B_DELTA = comparison.m_b_delta;
+        COMPARE_B_M_B = comparison.b.M_B;
+        COMPARE_B_M_L = comparison.b.M_L;
+        COMPARE_B_M_S = comparison.b.M_S;
+        COMPARE_B_M_N = comparison.b.M_N;
+        COMPARE_B_BLOCK_FEE = comparison.b.block_fee;
+        COMPARE_B_PENALTY = comparison.b.penalty;
+        COMPARE_B_MEMPOOL = comparison.b.mempool_size;
+    }
+    store_results(comparison.a);
+    summary
+}
+
+/// Analytic equilibrium for a constant demand (WASM wrapper)
+#[wasm_bindgen]
+pub struct EquilibriumResult {
+    pub demand: i64,
+    pub m_b: i64,
+    pub m_l: i64,
+    pub m_s: i64,
+    pub m_n: i64,
+    pub m_b_max: i64,
+    pub f_r: f64,
+    pub backlog_growth: i64,
+    /// Blocks to converge; -1 if beyond the solver's horizon
+    pub converge_blocks: i64,
+}
+
+/// Equilibrium M_B/M_L/M_S and fee for `demand` bytes per block, without
+/// running the simulation
+#[wasm_bindgen]
+pub fn solve_equilibrium(config: SimulationConfig, demand: i64) -> EquilibriumResult {
+    let eq = Equilibrium::solve(&config.into(), demand);
+    EquilibriumResult {
+        demand: eq.demand,
+        m_b: eq.m_b,
+        m_l: eq.m_l,
+        m_s: eq.m_s,
+        m_n: eq.m_n,
+        m_b_max: eq.m_b_max,
+        f_r: eq.f_r,
+        backlog_growth: eq.backlog_growth,
+        converge_blocks: eq.converge_blocks.map_or(-1, |blocks| blocks as i64),
     }
 }
 
@@ -1,5 +1,61 @@
 //! Core simulation logic (no WASM dependencies)
 
+use crate::behavior::{build_behavior, BehaviorContext, FeeBehaviorModel};
+use crate::constraints::{BlockLimit, ConstraintSummary, MlClamp};
+use crate::demand::DemandResponse;
+use crate::events::{EventTimeline, TimelineCursor};
+use crate::fork::{ForkTransition, WindowTransition};
+use crate::median::{median_of_sorted, median_of_unsorted};
+use crate::network::{NetworkModel, NetworkStats};
+use crate::sanity::{SanitySchedule, DEFAULT_SANITY_GROWTH_RATE};
+use crate::seed::WindowSeed;
+use crate::trace::{FullTrace, TraceRow};
+use std::ops::ControlFlow;
+
+/// Consensus rules for the penalty median M_N and the block weight limit M_B_max
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleSet {
+    /// NEW RULES: M_N = M_S, M_B_max = min(2*M_N, 16*M_L, A_C)
+    Current,
+    /// OLD RULES: M_N = min(M_S, mn_mult*M_L) (when `use_long_term_median_cap`), M_B_max = 2*M_N
+    Legacy,
+}
+
+impl RuleSet {
+    pub fn parse(name: &str) -> Option<RuleSet> {
+        match name {
+            "current" | "new" => Some(RuleSet::Current),
+            "legacy" | "old" => Some(RuleSet::Legacy),
+            _ => None,
+        }
+    }
+
+    /// Numeric id used by the CLI and the WASM wrapper (0 = current, 1 = legacy)
+    pub fn from_id(id: u32) -> RuleSet {
+        if id == 1 { RuleSet::Legacy } else { RuleSet::Current }
+    }
+
+    /// Median used for the penalty
+    pub fn penalty_median(self, config: &SimulationConfig, m_s: i64, m_l: i64) -> i64 {
+        match self {
+            RuleSet::Current => m_s,
+            RuleSet::Legacy if config.use_long_term_median_cap => {
+                let mn_cap = (config.mn_mult * m_l as f64) as i64;
+                m_s.min(mn_cap)
+            }
+            RuleSet::Legacy => m_s,
+        }
+    }
+
+    /// Maximum weight of the next block
+    pub fn max_block_weight(self, config: &SimulationConfig, m_n: i64, m_l: i64, sanity_cap: i64) -> i64 {
+        match self {
+            RuleSet::Current => (2 * m_n).min(config.long_term_cap(m_l)).min(sanity_cap),
+            RuleSet::Legacy => 2 * m_n,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct SimulationConfig {
     pub n: u32,
@@ -14,6 +70,8 @@ pub struct SimulationConfig {
     pub ramp_multiplier: f64,
     pub ml_mult: f64,
     pub mn_mult: f64,
+    // Multiple of M_L in M_B_max = min(2*M_N, 16*M_L, A_C)
+    pub ml_cap_mult: f64,
     pub add_noise: bool,
     pub users_pay_more: bool,
     pub simple_blocks: bool,
@@ -24,9 +82,170 @@ pub struct SimulationConfig {
     pub use_long_term_median_cap: bool,
     pub sanity_start_weight: i64,
     pub sanity_start_block: u32,
+    // Shape of A_C over time and its growth per 2-minute block
+    pub sanity_schedule: SanitySchedule,
+    pub sanity_growth_rate: f64,
+    // Record the per-block intermediates in `SimulationResults::trace`
+    pub full_trace: bool,
+    // Elastic demand (0 elasticity = exogenous demand)
+    pub demand_elasticity: f64,
+    pub delay_elasticity: f64,
+    pub demand_defer_fraction: f64,
+    // Counted in 2-minute blocks, like the demand curves
+    pub demand_memory: u32,
+    // Fee tier selection; `users_pay_more` selects PayMore when this is None
+    pub fee_behavior: FeeBehaviorModel,
+    // Block target time in seconds; time-based constants are derived from it
+    pub block_time: u32,
+    // Median window lengths in blocks (0 = 2 * mid_100k / 2 * mid_100)
+    pub long_window: u32,
+    pub short_window: u32,
+    // Multi-miner network (0 miners = single producer, no orphans)
+    pub miners: u32,
+    pub largest_miner_share: f64,
+    pub miner_bandwidth: f64,
+    pub propagation_latency: f64,
+    pub validation_rate: f64,
+    // Exponentially distributed block intervals with demand in wall-clock time
+    pub stochastic_intervals: bool,
+    // Consensus rules for M_N and M_B_max
+    pub rule_set: RuleSet,
+    // Hard fork to `fork_rule_set` at `fork_height` (0 = no fork)
+    pub fork_height: u32,
+    pub fork_rule_set: RuleSet,
+    pub fork_windows: WindowTransition,
+    // Mixed into the seeds of the noise, block interval and miner streams
+    // (0 = the fixed seeds of a plain run)
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            n: 20000,
+            steady_state: 1000000,  // NEW: 1MB (was 300kB)
+            z_m: 1000000,  // NEW: 1MB (was 300kB)
+            t_r: 10000,  // NEW: 10kB (was 3kB)
+            r_base: 0.6,
+            mid_100k: 50000,
+            mid_100: 50,
+            t_sim: 800,
+            run_type: 6,
+            ramp_multiplier: 3.0,
+            ml_mult: 2.0,
+            mn_mult: 50.0,
+            ml_cap_mult: 16.0,
+            add_noise: false,
+            users_pay_more: false,
+            simple_blocks: false,
+            large_sim_mode: false,
+            exact_median: false,
+            max_blocksize: 10_000_000,  // 10 MB default
+            max_blocksize_growth_rate: 0.0,  // 0% growth by default
+            use_long_term_median_cap: true,  // Use traditional M_N cap by default
+            sanity_start_weight: 10_000_000,  // NEW: 10MB sanity start weight
+            sanity_start_block: 0,  // NEW: Start from block 0
+            sanity_schedule: SanitySchedule::Compound,
+            sanity_growth_rate: DEFAULT_SANITY_GROWTH_RATE,  // ~40% annual growth
+            full_trace: false,
+            demand_elasticity: 0.0,
+            delay_elasticity: 0.0,
+            demand_defer_fraction: 0.5,  // Half of priced-out demand waits and comes back
+            demand_memory: 720,  // 1 day of 2-minute blocks
+            fee_behavior: FeeBehaviorModel::None,
+            block_time: 120,  // Monero: 2 minute blocks
+            long_window: 0,
+            short_window: 0,
+            miners: 0,
+            largest_miner_share: 0.3,
+            miner_bandwidth: 12_500_000.0,  // 100 Mbit/s
+            propagation_latency: 0.5,  // seconds
+            validation_rate: 2_000_000.0,  // bytes verified per second
+            stochastic_intervals: false,
+            rule_set: RuleSet::Current,
+            fork_height: 0,
+            fork_rule_set: RuleSet::Current,
+            fork_windows: WindowTransition::Carry,
+            seed: 0,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+/// Block time the original constants were written for
+pub const REFERENCE_BLOCK_TIME: u32 = 120;
+
+impl SimulationConfig {
+    /// Settings of original_python.py: 300 kB steady state and penalty free
+    /// zone, T_R 3000, M_L weight clamped to [M_L/1.7, 1.7*M_L], and the old
+    /// rules with M_N = min(M_S, 50*M_L) and M_B_max = 2*M_N. Medians are
+    /// exact, as with the script's bisect-maintained lists.
+    pub fn python_reference() -> Self {
+        SimulationConfig {
+            n: 100000,
+            steady_state: 300000,
+            z_m: 300000,
+            t_r: 3000,
+            ml_mult: 1.7,
+            mn_mult: 50.0,
+            use_long_term_median_cap: true,
+            exact_median: true,
+            rule_set: RuleSet::Legacy,
+            fork_rule_set: RuleSet::Legacy,
+            ..SimulationConfig::default()
+        }
+    }
+
+    /// Seed for a random stream with fixed seed `base`, varied by `seed`
+    pub(crate) fn stream_seed(&self, base: u64) -> u64 {
+        if self.seed == 0 { base } else { splitmix64(base ^ splitmix64(self.seed)).max(1) }
+    }
+
+    /// Length of the long term median window in blocks
+    pub fn long_window_len(&self) -> usize {
+        let len = if self.long_window > 0 { self.long_window as usize } else { 2 * self.mid_100k as usize };
+        len.max(1)
+    }
+
+    /// Length of the short term median window in blocks
+    pub fn short_window_len(&self) -> usize {
+        let len = if self.short_window > 0 { self.short_window as usize } else { 2 * self.mid_100 as usize };
+        len.max(1)
+    }
+
+    /// Blocks between re-sorts of the (long, short) median windows: every block
+    /// with `exact_median`, otherwise every half window
+    pub fn median_refresh_intervals(&self) -> (usize, usize) {
+        if self.exact_median {
+            (1, 1)
+        } else {
+            ((self.long_window_len() / 2).max(1000), (self.short_window_len() / 2).max(10))
+        }
+    }
+
+    /// The M_L term of M_B_max under the current rules (16*M_L by default)
+    pub fn long_term_cap(&self, m_l: i64) -> i64 {
+        (self.ml_cap_mult * m_l as f64) as i64
+    }
+
+    /// Blocks per day at the configured block time
+    pub fn blocks_per_day(&self) -> f64 {
+        86_400.0 / self.block_time.max(1) as f64
+    }
+
+    /// Blocks per (365 day) year at the configured block time
+    pub fn blocks_per_year(&self) -> f64 {
+        365.0 * self.blocks_per_day()
+    }
+
+    /// Convert a block count into the equivalent number of 2-minute blocks,
+    /// for constants that were calibrated on Monero's block time
+    pub fn reference_blocks(&self, blocks: f64) -> f64 {
+        blocks * self.block_time as f64 / REFERENCE_BLOCK_TIME as f64
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[allow(non_snake_case)]
 pub struct SimulationResults {
     pub max_mb: i64,
     pub max_penalty: f64,
@@ -42,16 +261,64 @@ pub struct SimulationResults {
     pub block_fee: Vec<f64>,
     pub penalty: Vec<f64>,
     pub mempool_size: Vec<i64>,
+    // Per fee tier (high = 16*f_r, low = f_r) in bytes; the pairs sum to
+    // input_volume and mempool_size
+    pub input_volume_high: Vec<i64>,
+    pub input_volume_low: Vec<i64>,
+    pub mempool_high: Vec<i64>,
+    pub mempool_low: Vec<i64>,
     pub indices: Vec<u32>,
+    // Seconds since the start of the run at which each block was found
+    pub timestamps: Vec<f64>,
+    // Median windows at the end of the run, for seeding a follow-up run
+    pub final_windows: WindowSeed,
+    // Per-miner outcomes and orphans when the network model is enabled
+    pub network: Option<NetworkStats>,
+    // Rule switches during the run and their transients
+    pub forks: Vec<ForkTransition>,
+    // Sanity cap A_C for each block (i64::MAX before it starts)
+    pub sanity_cap: Vec<i64>,
+    // Heights where A_C was the binding term of M_B_max
+    pub sanity_binding: Vec<u32>,
+    // What limited each block (`BlockLimit` ids) and how its M_L weight was
+    // clamped (`MlClamp` ids), with per-run counts
+    pub block_limit: Vec<u8>,
+    pub ml_clamp: Vec<u8>,
+    pub constraints: ConstraintSummary,
+    // Per-block intermediates when `full_trace` is set
+    pub trace: Option<FullTrace>,
+}
+
+/// Per-run data that doesn't fit in the `Copy` config
+#[derive(Clone, Debug, Default)]
+pub struct SimulationInputs {
+    /// Broadcast volume per block in bytes. When set it replaces the
+    /// `run_type` demand curve; blocks past the end repeat the last value.
+    pub demand_trace: Option<Vec<i64>>,
+    /// Initial median window contents. When unset both windows start filled
+    /// with `steady_state`.
+    pub window_seed: Option<WindowSeed>,
+    /// Miner population. When unset it is built from the `miners` config fields.
+    pub network: Option<NetworkModel>,
+    /// Shocks, parameter changes and rule switches applied at given heights
+    pub events: Option<EventTimeline>,
+}
+
+/// SplitMix64 finalizer: spreads nearby seeds over the whole u64 range
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 /// Simple pseudo-random number generator (xorshift)
-struct Rng {
+pub(crate) struct Rng {
     state: u64,
 }
 
 impl Rng {
-    fn new(seed: u64) -> Self {
+    pub(crate) fn new(seed: u64) -> Self {
         Rng { state: seed }
     }
     
@@ -64,12 +331,12 @@ impl Rng {
         x
     }
     
-    fn next_f64(&mut self) -> f64 {
+    pub(crate) fn next_f64(&mut self) -> f64 {
         (self.next() as f64) / (u64::MAX as f64)
     }
     
     // Box-Muller transform for normal distribution
-    fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+    pub(crate) fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
         let u1 = self.next_f64().max(1e-10);
         let u2 = self.next_f64();
         let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
@@ -77,18 +344,96 @@ impl Rng {
     }
 }
 
+/// Penalty a tx of `t_sim` bytes adds to a block already `m_b` bytes big
+#[inline]
+pub(crate) fn marginal_penalty(r_base: f64, m_b: i64, m_n: i64, t_sim: i64) -> f64 {
+    let b = (m_b as f64 / m_n as f64) - 1.0;
+    let mut t_t = t_sim as f64;
+    if t_t > (m_b - m_n) as f64 && m_b > m_n {
+        t_t = (m_b - m_n) as f64;
+    }
+    let b_t = t_t / m_n as f64;
+    if b + b_t <= 0.0 { 0.0 } else { r_base * (2.0 * b * b_t + b_t * b_t) }
+}
+
+/// Broadcast volume per block of the `run_type` demand curve at `t_ref`, the
+/// elapsed time in 2-minute blocks; type 5 floods at `m_b_max`. The curves were
+/// calibrated on Monero's block time, so every growth rate and delay is counted
+/// in reference blocks, and the volume, given per 2-minute block, is scaled to
+/// the block time. Demand in bytes per second is then the same at any block
+/// time.
+pub(crate) fn scheduled_demand(config: &SimulationConfig, t_ref: f64, m_b_max: i64) -> i64 {
+    let per_reference_block = match config.run_type {
+        1 => config.z_m + (100.0 * t_ref) as i64,
+        2 => config.z_m + (800.0 * t_ref) as i64,
+        3 => ((316.0 + (t_ref / 15.0)).powi(2)) as i64,
+        4 => (config.z_m as f64 * (1.6_f64.powf(9.8 + (t_ref / 50000.0)) - 99.75)) as i64,
+        5 => return m_b_max,
+        _ => {
+            // 6 (default): configurable ramp to sine
+            let start_val: i64 = 300000;
+            let ramp_delay = 10.0;
+            let ramp_days = 14.0;
+            let ramp_time = ramp_days * 86_400.0 / REFERENCE_BLOCK_TIME as f64;
+            
+            if t_ref <= ramp_delay {
+                start_val
+            } else if t_ref <= ramp_delay + ramp_time {
+                let slope = ((config.ramp_multiplier - 1.0) * start_val as f64 / ramp_time) as i64;
+                start_val + (slope as f64 * (t_ref - ramp_delay)) as i64
+            } else {
+                (config.ramp_multiplier * start_val as f64 + 220.0 * (t_ref / 802.0).sin() * 800.0) as i64
+            }
+        }
+    };
+    if config.block_time == REFERENCE_BLOCK_TIME {
+        per_reference_block
+    } else {
+        (per_reference_block as f64 * config.block_time as f64 / REFERENCE_BLOCK_TIME as f64) as i64
+    }
+}
+
 /// Main simulation function - core logic without WASM dependencies
 pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
+    run_simulation_with_inputs(config, &SimulationInputs::default())
+}
+
+/// Run the simulation with additional per-run inputs (e.g. replayed demand)
+pub fn run_simulation_with_inputs(config: SimulationConfig, inputs: &SimulationInputs) -> SimulationResults {
+    run_simulation_observed(config, inputs, |_| ControlFlow::Continue(()))
+}
+
+/// One block as the engine produced it, for watching a run in progress
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlockSnapshot {
+    pub height: u32,
+    pub m_b: i64,
+    pub m_l: i64,
+    pub m_s: i64,
+    pub m_n: i64,
+    pub m_b_max: i64,
+    /// Bytes broadcast for and left over after this block
+    pub input_volume: i64,
+    pub mempool: i64,
+    pub block_fee: f64,
+    pub penalty: f64,
+}
+
+/// Run the simulation, calling `observer` after every block. Returning
+/// `Break` ends the run there; the results cover the blocks run so far.
+pub fn run_simulation_observed(
+    mut config: SimulationConfig,
+    inputs: &SimulationInputs,
+    mut observer: impl FnMut(&BlockSnapshot) -> ControlFlow<()>,
+) -> SimulationResults {
     let n = config.n as usize;
-    let mid_100k = config.mid_100k as usize;
-    let mid_100 = config.mid_100 as usize;
-    let len_l = mid_100k * 2;
-    let len_s = mid_100 * 2;
+    let len_l = config.long_window_len();
+    let len_s = config.short_window_len();
     
     // Initialize
     let mut m_b: i64 = 0;
     let mut m_l_prev = config.steady_state;
-    let mut rng = Rng::new(12345);
+    let mut rng = Rng::new(config.stream_seed(12345));
     
     // Circular buffers for median calculation
     let mut m_l_buffer: Vec<i64> = vec![config.steady_state; len_l];
@@ -96,22 +441,24 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
     let mut m_s_buffer: Vec<i64> = vec![config.steady_state; len_s];
     let mut m_s_head: usize = 0;
     
+    // Start from seeded history instead of a flat steady state
+    if let Some(seed) = &inputs.window_seed {
+        m_l_buffer = WindowSeed::fit(&seed.long_weights, len_l, config.steady_state);
+        m_s_buffer = WindowSeed::fit(&seed.short_weights, len_s, config.steady_state);
+        m_b = seed.last_block_weight;
+        
+        let mut sorted = m_l_buffer.clone();
+        sorted.sort_unstable();
+        m_l_prev = median_of_sorted(&sorted);
+    }
+    
     // For median calculation, we'll sort periodically
-    let mut m_l_sorted: Vec<i64> = vec![config.steady_state; len_l];
-    let mut m_s_sorted: Vec<i64> = vec![config.steady_state; len_s];
+    let mut m_l_sorted: Vec<i64> = m_l_buffer.clone();
+    let mut m_s_sorted: Vec<i64> = m_s_buffer.clone();
     
-    // PERFORMANCE FIX: Sort interval depends on exact_median flag
-    let sort_interval_l = if config.exact_median {
-        1  // Sort every update (matches Python bisect behavior)
-    } else {
-        (len_l / 2).max(1000)  // Fast mode: sort every 50k updates
-    };
-
-    let sort_interval_s = if config.exact_median {
-        1  // Sort every update (matches Python bisect behavior)
-    } else {
-        (len_s / 2).max(10)  // Fast mode: sort every 50 updates
-    };
+    // PERFORMANCE FIX: Sort interval depends on exact_median flag (every
+    // update matches Python bisect behavior; fast mode sorts every half window)
+    let (sort_interval_l, sort_interval_s) = config.median_refresh_intervals();
 
     let mut updates_since_sort_l: usize = 0;
     let mut updates_since_sort_s: usize = 0;
@@ -130,7 +477,30 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
     
     // Mempool
     let mut mempool: [i64; 2] = [0, 0];
-    let mut percent_response: f64 = 0.0;
+    let mut behavior = build_behavior(&config);
+
+    // Multi-miner network state
+    let network = inputs
+        .network
+        .clone()
+        .or_else(|| NetworkModel::from_config(&config))
+        .filter(|model| !model.miners.is_empty());
+    let mut network_stats = network.as_ref().map(|model| NetworkStats::new(model, n));
+    let mut network_rng = Rng::new(config.stream_seed(0x005e_ed0f_0b1a));
+    let block_time = config.block_time.max(1) as f64;
+    
+    // Block timing: fixed intervals or Poisson arrivals
+    let mut interval_rng = Rng::new(config.stream_seed(0x0000_b10c_71e5));
+    let mut elapsed: f64 = 0.0;
+    
+    // Scripted events and hard forks
+    let mut timeline = TimelineCursor::new(inputs.events.as_ref());
+    let mut forks: Vec<ForkTransition> = Vec::new();
+    let mut prev_m_b_max: i64 = 0;
+    let mut prev_m_n: i64 = 0;
+    
+    // Elastic demand state
+    let mut demand = DemandResponse::new(&config);
     
     // Results tracking
     let mut max_mb: i64 = 0;
@@ -151,13 +521,66 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
     let mut block_fee_data: Vec<f64> = Vec::with_capacity(data_points);
     let mut penalty_data: Vec<f64> = Vec::with_capacity(data_points);
     let mut mempool_size_data: Vec<i64> = Vec::with_capacity(data_points);
+    let mut input_volume_tier_data: [Vec<i64>; 2] = [Vec::with_capacity(data_points), Vec::with_capacity(data_points)];
+    let mut mempool_tier_data: [Vec<i64>; 2] = [Vec::with_capacity(data_points), Vec::with_capacity(data_points)];
     let mut indices_data: Vec<u32> = Vec::with_capacity(data_points);
+    let mut timestamps_data: Vec<f64> = Vec::with_capacity(data_points);
+    let mut sanity_cap_data: Vec<i64> = Vec::with_capacity(data_points);
+    let mut sanity_binding_data: Vec<u32> = Vec::new();
+    let mut block_limit_data: Vec<u8> = Vec::with_capacity(data_points);
+    let mut ml_clamp_data: Vec<u8> = Vec::with_capacity(data_points);
+    let mut constraints = ConstraintSummary::default();
+    let mut trace = config.full_trace.then(|| FullTrace::with_capacity(data_points));
     
     for i in 0..n {
         // ============================================
         // 1. MEDIAN CALCULATIONS
         // ============================================
         
+        // Apply scripted events due at this height
+        let rules_before = config.rule_set;
+        let fee_params_before = (config.r_base, config.t_r);
+        let mut transition = timeline.advance(i as u32, &mut config);
+        if (config.r_base, config.t_r) != fee_params_before {
+            demand.reprice(&config);
+        }
+        if config.fork_height > 0 && i == config.fork_height as usize {
+            config.rule_set = config.fork_rule_set;
+            transition = Some(config.fork_windows);
+        }
+        
+        // Hard fork: carry over or reinterpret the windows built under the old rules
+        if let Some(windows) = transition {
+            // A fork at the first block is measured against what the old rules
+            // give for the windows the run starts from
+            let (m_n_before, m_b_max_before) = if i == 0 {
+                let m_l = median_of_unsorted(&mut m_l_buffer.clone());
+                let m_s = median_of_unsorted(&mut m_s_buffer.clone());
+                let m_n = rules_before.penalty_median(&config, m_s, m_l);
+                let sanity_cap = config.sanity_schedule.cap(&config, 0, m_l);
+                (m_n, rules_before.max_block_weight(&config, m_n, m_l, sanity_cap))
+            } else {
+                (prev_m_n, prev_m_b_max)
+            };
+            let new_m_l = windows.apply(&config, &mut m_l_buffer, &mut m_s_buffer);
+            if windows != WindowTransition::Carry {
+                m_l_prev = new_m_l;
+            }
+            updates_since_sort_l = sort_interval_l;
+            updates_since_sort_s = sort_interval_s;
+            forks.push(ForkTransition {
+                height: i as u32,
+                from: rules_before,
+                to: config.rule_set,
+                windows,
+                m_n_before,
+                m_b_max_before,
+                m_b_max_after: 0,
+                peak_m_n_deviation: 0.0,
+                settle_blocks: None,
+            });
+        }
+        
         // Periodic sort for M_L
         if updates_since_sort_l >= sort_interval_l || i == 0 {
             m_l_sorted.copy_from_slice(&m_l_buffer);
@@ -172,42 +595,42 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
             updates_since_sort_s = 0;
         }
         
-        // Calculate medians
-        let m_l = (m_l_sorted[mid_100k] + m_l_sorted[len_l - 1 - mid_100k]) / 2;
-        let m_s = (m_s_sorted[mid_100] + m_s_sorted[len_s - 1 - mid_100]) / 2;
+        // Calculate medians (odd or even window lengths)
+        let m_l = median_of_sorted(&m_l_sorted);
+        let m_s = median_of_sorted(&m_s_sorted);
         
         // M_L_weight calculation
         let ml_upper = (config.ml_mult * m_l_prev as f64) as i64;
         let ml_lower = (m_l_prev as f64 / config.ml_mult) as i64;
         let m_l_weight = m_b.min(ml_upper).max(config.z_m).max(ml_lower);
+        let ml_clamp = MlClamp::classify(m_b, ml_upper, config.z_m, ml_lower);
         
         // M_S_weight calculation
         let m_s_weight = m_b.max(m_l);
         
         // M_N calculation - NEW RULES: M_N = M_S (no cap)
-        // OLD: let mn_cap = (config.mn_mult * m_l as f64) as i64;
-        // OLD: let m_n = m_s.min(mn_cap);
-        let m_n = m_s;
+        // OLD: M_N = min(M_S, mn_mult * M_L)
+        let m_n = config.rule_set.penalty_median(&config, m_s, m_l);
         
         // Sanity cap calculation: A_C = A_S * (1 + 5/(4*10^6))^(K_B - K_S)
         // A_S = sanity_start_weight (default 10000000 bytes)
         // K_B = current block number (i)
         // K_S = sanity_start_block
-        let sanity_cap = if i >= config.sanity_start_block as usize {
-            let blocks_elapsed = (i - config.sanity_start_block as usize) as f64;
-            let growth_rate: f64 = 5.0 / (4.0 * 1_000_000.0); // ~40% annual growth
-            let base: f64 = 1.0 + growth_rate;
-            let a_c = (config.sanity_start_weight as f64 * base.powf(blocks_elapsed)) as i64;
-            a_c
-        } else {
-            // Before sanity start block, use a very large value (effectively no cap)
-            i64::MAX
-        };
+        // Other schedules are in sanity.rs; before K_S there is no cap.
+        let sanity_cap = config.sanity_schedule.cap(&config, i, m_l);
         
         // M_B_max calculation - NEW RULES: min(2*M_N, 16*M_L, A_C)
         // OLD: M_B_max = 2*M_N with optional cap at 50*M_L or 100*M_L
         // NEW: M_B_max = min(2*M_N, 16*M_L, A_C)
-        let m_b_max = (2 * m_n).min(16 * m_l).min(sanity_cap);
+        let m_b_max = config.rule_set.max_block_weight(&config, m_n, m_l, sanity_cap);
+        if let (Some(_), Some(fork)) = (transition, forks.last_mut()) {
+            fork.m_b_max_after = m_b_max;
+        }
+        prev_m_b_max = m_b_max;
+        prev_m_n = m_n;
+        if config.rule_set == RuleSet::Current && sanity_cap < i64::MAX && sanity_cap <= (2 * m_n).min(config.long_term_cap(m_l)) {
+            sanity_binding_data.push(i as u32);
+        }
         
         // ============================================
         // LARGE_SIMULATION_MODE: Dynamic T_sim Scaling
@@ -241,16 +664,14 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
             }
             
             // Reset mechanism: if blocks are stuck at same size
-            if i > 100 {
-                if m_b_archive[i - 1] == m_b_archive[i - 60] {
-                    t_sim_reset_counter += 1;
-                    if t_sim_reset_counter > 20 && t_sim > 800 && m_s < m_n + t_sim {
-                        // Decrease T_sim to unstick the simulation
-                        mempool[0] *= 2;
-                        mempool[1] *= 2;
-                        t_sim /= 2;
-                        t_sim_reset_counter = 0;
-                    }
+            if i > 100 && m_b_archive[i - 1] == m_b_archive[i - 60] {
+                t_sim_reset_counter += 1;
+                if t_sim_reset_counter > 20 && t_sim > 800 && m_s < m_n + t_sim {
+                    // Decrease T_sim to unstick the simulation
+                    mempool[0] *= 2;
+                    mempool[1] *= 2;
+                    t_sim /= 2;
+                    t_sim_reset_counter = 0;
                 }
             }
         }
@@ -263,28 +684,37 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
         // ============================================
         let mut broadcast: [i64; 2] = [0, 0];
         
-        let vol: i64 = match config.run_type {
-            1 => config.z_m + 100 * (i as i64),
-            2 => config.z_m + 800 * (i as i64),
-            3 => ((316.0 + (i as f64 / 15.0)).powi(2)) as i64,
-            4 => (config.z_m as f64 * (1.6_f64.powf(9.8 + (i as f64 / 50000.0)) - 99.75)) as i64,
-            5 => m_b_max,
-            6 | _ => {
-                let start_val: i64 = 300000;
-                let ramp_delay: usize = 10;
-                let ramp_days: usize = 14;
-                let ramp_time = ramp_days * 720;
-                
-                if i <= ramp_delay {
-                    start_val
-                } else if i <= ramp_delay + ramp_time {
-                    start_val + (((config.ramp_multiplier - 1.0) * start_val as f64 / ramp_time as f64) as i64) * ((i - ramp_delay) as i64)
-                } else {
-                    (config.ramp_multiplier * start_val as f64 + 220.0 * (i as f64 / 802.0).sin() * 800.0) as i64
-                }
-            }
+        // Time until this block is found
+        let interval = if config.stochastic_intervals {
+            -(1.0 - interval_rng.next_f64()).max(1e-12).ln() * block_time
+        } else {
+            block_time
         };
         
+        // Demand curves follow wall-clock time, calibrated on 2-minute blocks
+        let t_ref = config.reference_blocks(elapsed / block_time);
+        let demand_trace = inputs.demand_trace.as_deref().filter(|trace| !trace.is_empty());
+        let vol: i64 = if let Some(trace) = demand_trace {
+            trace[i.min(trace.len() - 1)]
+        } else {
+            scheduled_demand(&config, t_ref, m_b_max)
+        };
+        
+        // Scripted demand shocks
+        let demand_factor = timeline.demand_factor();
+        let vol = if demand_factor != 1.0 { (vol as f64 * demand_factor) as i64 } else { vol };
+        
+        // Elastic demand: scale exogenous volume by recent fees and delay
+        let vol = if demand.is_active() { demand.apply(vol) } else { vol };
+        
+        // Volume is per nominal block; longer gaps accumulate more demand
+        let vol = if config.stochastic_intervals {
+            (vol as f64 * interval / block_time) as i64
+        } else {
+            vol
+        };
+        elapsed += interval;
+        
         broadcast[1] = vol / t_sim;
         
         // Add noise if enabled
@@ -293,117 +723,164 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
             broadcast[1] = (broadcast[1] + noise as i64).max(1);
         }
         
-        // Users pay more if enabled
-        if config.users_pay_more && broadcast[1] > 0 {
-            let prev_resp = percent_response;
-            let calc = (mempool[1] as f64 / (3.0 * broadcast[1] as f64) * 100.0).floor();
-            percent_response = (prev_resp + 0.1 * (calc - prev_resp)).floor();
-            percent_response = percent_response.clamp(0.0, 100.0);
-            
-            if percent_response > 0.0 {
-                broadcast[0] = ((broadcast[1] as f64 * percent_response) / 100.0) as i64;
-                broadcast[1] = ((broadcast[1] as f64 * (100.0 - percent_response)) / 100.0) as i64;
-            }
+        // Fee levels
+        let fees: [f64; 2] = [16.0 * f_r * t_sim as f64, f_r * t_sim as f64];
+        
+        // Fee behavior: split new tx between tiers, possibly re-tier queued tx
+        if let Some(behavior) = behavior.as_mut() {
+            let ctx = BehaviorContext {
+                t_sim,
+                z_m: config.z_m,
+                t_r: config.t_r,
+                r_base: config.r_base,
+                m_l,
+                m_n,
+                m_b_max,
+                fees,
+            };
+            behavior.respond(&ctx, &mut broadcast, &mut mempool);
         }
         
+        // Scripted attackers flood at the high fee level
+        broadcast[0] += timeline.spam_bytes() / t_sim;
+        
         // Update mempool
         mempool[0] += broadcast[0];
         mempool[1] += broadcast[1];
         
-        // Fee levels
-        let fees: [f64; 2] = [16.0 * f_r * t_sim as f64, f_r * t_sim as f64];
-        
         // ============================================
         // 3. BUILD BLOCK
         // ============================================
-        let mut block_fee_total: f64 = 0.0;
-        
-        if config.simple_blocks {
-            // Simple mode: just fill block from mempool
-            let mempool_total_bytes = (mempool[0] + mempool[1]) * t_sim;
-            m_b = m_b_max.min(mempool_total_bytes);
+        // A block that loses the orphan race is re-mined by the rival, from
+        // the mempool as it was before the orphaned block
+        let queued = mempool;
+        let mut miner = network.as_ref().map(|model| model.pick_miner(network_rng.next_f64()));
+        let mut raced = false;
+        let (block_fee_total, blockfilled, last_f_t) = loop {
+            let orphan_aware = network.as_ref().filter(|model| model.orphan_aware).zip(miner);
             
-            // Approximate fees
-            let b_final_approx = (m_b as f64 / m_n as f64) - 1.0;
-            if b_final_approx > 0.0 {
-                block_fee_total = config.r_base * b_final_approx * b_final_approx;
-            }
-            
-            // Remove from mempool
-            let mut tx_to_remove = (m_b + t_sim - 1) / t_sim; // ceil division
-            let remove_from_high = mempool[0].min(tx_to_remove);
-            mempool[0] -= remove_from_high;
-            tx_to_remove -= remove_from_high;
-            mempool[1] = (mempool[1] - tx_to_remove).max(0);
-        } else {
-            // Detailed mode: per-tx fee calculation
-            m_b = 0;
+            let mut block_fee_total: f64 = 0.0;
             let mut blockfilled: [i64; 2] = [0, 0];
-            let mut break_flag = false;
+            let mut last_f_t: f64 = 0.0;
             
-            for k in 0..2 {
-                if break_flag { break; }
+            if config.simple_blocks {
+                // Simple mode: just fill block from mempool
+                let mempool_total_bytes = (mempool[0] + mempool[1]) * t_sim;
+                m_b = m_b_max.min(mempool_total_bytes);
+            
+                // Approximate fees
+                let b_final_approx = (m_b as f64 / m_n as f64) - 1.0;
+                if b_final_approx > 0.0 {
+                    block_fee_total = config.r_base * b_final_approx * b_final_approx;
+                }
+            
+                // Remove from mempool
+                let mut tx_to_remove = (m_b + t_sim - 1) / t_sim; // ceil division
+                let remove_from_high = mempool[0].min(tx_to_remove);
+                mempool[0] -= remove_from_high;
+                tx_to_remove -= remove_from_high;
+                mempool[1] = (mempool[1] - tx_to_remove).max(0);
+            } else {
+                // Detailed mode: per-tx fee calculation
+                m_b = 0;
+                let mut break_flag = false;
+            
+                for k in 0..2 {
+                    if break_flag { break; }
                 
-                for l in 0..mempool[k] {
-                    if m_b >= m_b_max {
-                        blockfilled[0] = k as i64;
-                        blockfilled[1] = l;
-                        break_flag = true;
-                        break;
-                    }
+                    for l in 0..mempool[k] {
+                        if m_b >= m_b_max {
+                            blockfilled[0] = k as i64;
+                            blockfilled[1] = l;
+                            break_flag = true;
+                            break;
+                        }
                     
-                    let b = (m_b as f64 / m_n as f64) - 1.0;
-                    let mut t_t = t_sim as f64;
-                    if t_t > (m_b - m_n) as f64 && m_b > m_n {
-                        t_t = (m_b - m_n) as f64;
-                    }
-                    let b_t = t_t / m_n as f64;
-                    let mut f_t = config.r_base * (2.0 * b * b_t + b_t * b_t);
-                    if b + b_t <= 0.0 { f_t = 0.0; }
+                        let mut f_t = marginal_penalty(config.r_base, m_b, m_n, t_sim);
                     
-                    if fees[k] < f_t {
-                        blockfilled[0] = k as i64;
-                        blockfilled[1] = l;
-                        break_flag = true;
-                        break;
-                    }
+                        // Orphan risk from the extra propagation time is a cost too
+                        if let Some((model, miner)) = orphan_aware {
+                            f_t += model.marginal_orphan_cost(miner, m_b, t_sim, config.r_base, block_time);
+                        }
+                        last_f_t = f_t;
+                    
+                        if fees[k] < f_t {
+                            blockfilled[0] = k as i64;
+                            blockfilled[1] = l;
+                            break_flag = true;
+                            break;
+                        }
                     
-                    m_b += t_sim;
+                        m_b += t_sim;
+                    }
                 }
-            }
             
-            // Handle case where all tx were processed
-            if blockfilled[0] == 0 && blockfilled[1] == 0 {
-                if mempool[1] != 0 {
-                    blockfilled[0] = 1;
-                    blockfilled[1] = mempool[1];
-                } else if mempool[0] != 0 {
-                    blockfilled[0] = 0;
-                    blockfilled[1] = mempool[0];
+                // Handle case where all tx were processed
+                if blockfilled[0] == 0 && blockfilled[1] == 0 {
+                    if mempool[1] != 0 {
+                        blockfilled[0] = 1;
+                        blockfilled[1] = mempool[1];
+                    } else if mempool[0] != 0 {
+                        blockfilled[0] = 0;
+                        blockfilled[1] = mempool[0];
+                    }
                 }
-            }
             
-            // Calculate fees
-            for k in 0..(blockfilled[0] as usize) {
-                block_fee_total += mempool[k] as f64 * fees[k];
+                // Calculate fees
+                for k in 0..(blockfilled[0] as usize) {
+                    block_fee_total += mempool[k] as f64 * fees[k];
+                }
+                block_fee_total += (blockfilled[1] - 1).max(0) as f64 * fees[blockfilled[0] as usize];
+            
+                // Remove from mempool
+                for slot in mempool.iter_mut().take(blockfilled[0] as usize) {
+                    *slot = 0;
+                }
+                mempool[blockfilled[0] as usize] = (mempool[blockfilled[0] as usize] - blockfilled[1]).max(0);
             }
-            block_fee_total += (blockfilled[1] - 1).max(0) as f64 * fees[blockfilled[0] as usize];
             
-            // Remove from mempool
-            for k in 0..(blockfilled[0] as usize) {
-                mempool[k] = 0;
+            // Orphan race: the rest of the network finds a competing block after
+            // an exponential interval at its share of the hashrate. If that comes
+            // before this block has propagated, the rival takes the height. The
+            // rival's own block is not raced again.
+            if let (Some(model), Some(stats), Some(k)) = (network.as_ref(), network_stats.as_mut(), miner) {
+                stats.miners[k].blocks_mined += 1;
+                stats.miners[k].bytes_mined += m_b;
+                if !raced {
+                    raced = true;
+                    let competing = 1.0 - model.miners[k].hashrate_share;
+                    let rival_after = -(1.0 - network_rng.next_f64()).max(1e-12).ln() * block_time / competing.max(1e-12);
+                    if rival_after < model.propagation_delay(k, m_b) {
+                        stats.miners[k].blocks_orphaned += 1;
+                        stats.orphaned_blocks.push(i as u32);
+                        mempool = queued;
+                        elapsed += rival_after;
+                        miner = Some(model.pick_rival(k, network_rng.next_f64()));
+                        continue;
+                    }
+                }
+                stats.block_miner.push(k as u32);
+                stats.orphan_probability.push(model.orphan_probability(k, m_b, block_time));
             }
-            mempool[blockfilled[0] as usize] = (mempool[blockfilled[0] as usize] - blockfilled[1]).max(0);
-        }
+            break (block_fee_total, blockfilled, last_f_t);
+        };
         
         cumulative_fees += block_fee_total;
         
+        let block_limit = BlockLimit::classify(&config, m_n, m_l, sanity_cap, m_b, m_b_max, mempool[0] + mempool[1]);
+        constraints.record(block_limit, ml_clamp);
+        
         // ============================================
         // 4. PENALTY CALCULATION
         // ============================================
         let b_final = (m_b as f64 / m_n as f64) - 1.0;
         let p_b = if b_final > 0.0 { config.r_base * b_final * b_final } else { 0.0 };
         
+        // Revenue of the miner whose block made it into the chain
+        if let (Some(stats), Some(miner)) = (network_stats.as_mut(), miner) {
+            stats.miners[miner].revenue += (config.r_base - p_b).max(0.0) + block_fee_total;
+        }
+        
         // ============================================
         // 5. UPDATE MEDIAN BUFFERS
         // ============================================
@@ -417,6 +894,18 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
         
         m_l_prev = m_l;
         
+        // Feed the block outcome back into elastic demand
+        if demand.is_active() {
+            // Simple blocks carry the penalty in block_fee_total, not fees paid
+            let fee_per_byte = if m_b > 0 && !config.simple_blocks {
+                Some((block_fee_total / m_b as f64).max(f_r))
+            } else {
+                None
+            };
+            let delay_blocks = ((mempool[0] + mempool[1]) * t_sim) as f64 / m_b_max.max(1) as f64;
+            demand.observe(fee_per_byte, delay_blocks);
+        }
+        
         // Store M_B for LARGE_SIMULATION_MODE reset detection
         if config.large_sim_mode {
             m_b_archive.push(m_b);
@@ -440,16 +929,61 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
             block_fee_data.push(block_fee_total);
             penalty_data.push(p_b);
             mempool_size_data.push(mempool_size_bytes);
+            for k in 0..2 {
+                input_volume_tier_data[k].push(broadcast[k] * t_sim);
+                mempool_tier_data[k].push(mempool[k] * t_sim);
+            }
             indices_data.push(i as u32);
+            timestamps_data.push(elapsed);
+            sanity_cap_data.push(sanity_cap);
+            block_limit_data.push(block_limit as u8);
+            ml_clamp_data.push(ml_clamp as u8);
+            if let Some(trace) = trace.as_mut() {
+                trace.push(TraceRow {
+                    m_l_weight,
+                    m_s_weight,
+                    m_b_max,
+                    f_r,
+                    last_f_t,
+                    mempool,
+                    blockfilled,
+                    percent_response: behavior.as_ref().map_or(0.0, |b| b.percent_response()),
+                });
+            }
         }
+        
+        let snapshot = BlockSnapshot {
+            height: i as u32,
+            m_b,
+            m_l,
+            m_s,
+            m_n,
+            m_b_max,
+            input_volume: (broadcast[0] + broadcast[1]) * t_sim,
+            mempool: mempool_size_bytes,
+            block_fee: block_fee_total,
+            penalty: p_b,
+        };
+        if observer(&snapshot).is_break() {
+            break;
+        }
+    }
+    
+    // Transient after each fork lasts until the next one
+    for k in 0..forks.len() {
+        let end = forks.get(k + 1).map_or(m_n_data.len(), |next| next.height as usize);
+        forks[k].measure(&m_n_data, end);
     }
     
+    let [input_volume_high, input_volume_low] = input_volume_tier_data;
+    let [mempool_high, mempool_low] = mempool_tier_data;
+    
     SimulationResults {
         max_mb,
         max_penalty,
         max_mempool,
         cumulative_fees,
-        data_points: data_points as u32,
+        data_points: m_b_data.len() as u32,
         M_B: m_b_data,
         M_L: m_l_data,
         M_S: m_s_data,
@@ -458,6 +992,24 @@ pub fn run_simulation_core(config: SimulationConfig) -> SimulationResults {
         block_fee: block_fee_data,
         penalty: penalty_data,
         mempool_size: mempool_size_data,
+        input_volume_high,
+        input_volume_low,
+        mempool_high,
+        mempool_low,
         indices: indices_data,
+        timestamps: timestamps_data,
+        final_windows: WindowSeed {
+            long_weights: [&m_l_buffer[m_l_head..], &m_l_buffer[..m_l_head]].concat(),
+            short_weights: [&m_s_buffer[m_s_head..], &m_s_buffer[..m_s_head]].concat(),
+            last_block_weight: m_b,
+        },
+        network: network_stats,
+        forks,
+        sanity_cap: sanity_cap_data,
+        sanity_binding: sanity_binding_data,
+        block_limit: block_limit_data,
+        ml_clamp: ml_clamp_data,
+        constraints,
+        trace,
     }
 }
\ No newline at end of file
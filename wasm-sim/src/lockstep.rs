@@ -0,0 +1,564 @@
+//! Lockstep engine for sweeps over `simple_blocks` configs
+//!
+//! In simple block mode a block is a few arithmetic steps plus the median
+//! windows, so most of the scalar engine's time goes to bookkeeping that
+//! sweeps never read: per-block series, constraint tracking, and demand and
+//! sanity curves recomputed for every run. This engine advances up to `LANES`
+//! runs together, one block at a time, and keeps only their `RunTotals`. Run
+//! state is held as structure-of-arrays lanes, demand and sanity curves and
+//! starting windows are computed once per distinct setting in a batch, and
+//! the rule caps that only move with the medians are recomputed when those
+//! are refreshed. The per-lane steps are straight loops over the lane arrays,
+//! left to the compiler to vectorize as far as the target allows (i64
+//! min/max and f64 to i64 conversions need AVX-512 on x86).
+//!
+//! Results are identical to the scalar engine's. Runs it doesn't cover (see
+//! `supported`) go through the scalar engine in the same batch.
+
+use crate::batch::parallel_map;
+use crate::behavior::FeeBehaviorModel;
+use crate::demand::DemandResponse;
+use crate::lib_core::{
+    run_simulation_with_inputs, scheduled_demand, Rng, RuleSet, SimulationConfig, SimulationInputs, SimulationResults,
+};
+use crate::median::{median_of_unsorted, MedianWindow};
+use crate::sanity::SanitySchedule;
+use crate::seed::WindowSeed;
+
+/// Runs advanced together by one worker
+pub const LANES: usize = 16;
+
+/// The numbers sweeps read from a run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunTotals {
+    pub blocks: u32,
+    pub max_mb: i64,
+    pub max_penalty: f64,
+    pub max_mempool: i64,
+    pub cumulative_fees: f64,
+    /// M_B of the last block (0 for an empty run)
+    pub final_mb: i64,
+    /// First height at which M_B reached the target size
+    pub target_reached_at: Option<u32>,
+}
+
+impl RunTotals {
+    pub fn from_results(results: &SimulationResults, target_size: i64) -> RunTotals {
+        RunTotals {
+            blocks: results.M_B.len() as u32,
+            max_mb: results.max_mb,
+            max_penalty: results.max_penalty,
+            max_mempool: results.max_mempool,
+            cumulative_fees: results.cumulative_fees,
+            final_mb: results.M_B.last().copied().unwrap_or(0),
+            target_reached_at: results.M_B.iter().position(|&m_b| m_b >= target_size).map(|k| results.indices[k]),
+        }
+    }
+}
+
+/// Whether the lockstep engine can run `config`: simple blocks without the
+/// features that carry per-run state beyond the windows and the mempool
+pub fn supported(config: &SimulationConfig, inputs: &SimulationInputs) -> bool {
+    config.simple_blocks
+        && config.t_sim > 0
+        && !config.large_sim_mode
+        && !config.stochastic_intervals
+        && config.fork_height == 0
+        && config.miners == 0
+        && FeeBehaviorModel::resolve(config) == FeeBehaviorModel::None
+        && !DemandResponse::new(config).is_active()
+        && inputs.events.is_none()
+        && inputs.network.is_none()
+}
+
+/// Totals of every config on the same inputs with up to `threads` threads
+/// (0 = one per core), in config order. Supported configs that share a run
+/// length and median windows are run in lockstep, the rest one at a time.
+pub fn run_totals(
+    configs: &[SimulationConfig],
+    inputs: &SimulationInputs,
+    threads: usize,
+    target_size: i64,
+) -> Vec<RunTotals> {
+    let mut groups: Vec<(LaneShape, Vec<usize>)> = Vec::new();
+    let mut work: Vec<Work> = Vec::new();
+    for (k, config) in configs.iter().enumerate() {
+        if !supported(config, inputs) {
+            work.push(Work::Scalar(k));
+            continue;
+        }
+        let shape = LaneShape::of(config);
+        match groups.iter_mut().find(|(s, _)| *s == shape) {
+            Some((_, members)) => members.push(k),
+            None => groups.push((shape, vec![k])),
+        }
+    }
+    for (group, (_, members)) in groups.iter().enumerate() {
+        work.extend((0..members.len()).step_by(LANES).map(|start| Work::Lanes(group, start)));
+    }
+
+    let setups = parallel_map(&groups, threads, |_, (shape, members)| {
+        let group: Vec<SimulationConfig> = members.iter().map(|&k| configs[k]).collect();
+        GroupSetup::new(*shape, &group, inputs)
+    });
+    let done = parallel_map(&work, threads, |_, &work| match work {
+        Work::Scalar(k) => {
+            vec![(k, RunTotals::from_results(&run_simulation_with_inputs(configs[k], inputs), target_size))]
+        }
+        Work::Lanes(group, start) => {
+            let (shape, members) = &groups[group];
+            let members = &members[start..(start + LANES).min(members.len())];
+            let lane_configs: Vec<SimulationConfig> = members.iter().map(|&k| configs[k]).collect();
+            let totals = run_lanes(*shape, &lane_configs, &setups[group], start, inputs, target_size);
+            members.iter().copied().zip(totals).collect()
+        }
+    });
+    let mut totals = vec![RunTotals::default(); configs.len()];
+    for (k, run) in done.into_iter().flatten() {
+        totals[k] = run;
+    }
+    totals
+}
+
+/// A batch job: one run on the scalar engine, or the chunk of a group's
+/// lanes from a position in the group
+#[derive(Clone, Copy)]
+enum Work {
+    Scalar(usize),
+    Lanes(usize, usize),
+}
+
+/// What lanes run together must share: the block loop and window layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LaneShape {
+    n: u32,
+    long_window: usize,
+    short_window: usize,
+    exact_median: bool,
+}
+
+impl LaneShape {
+    fn of(config: &SimulationConfig) -> LaneShape {
+        LaneShape {
+            n: config.n,
+            long_window: config.long_window_len(),
+            short_window: config.short_window_len(),
+            exact_median: config.exact_median,
+        }
+    }
+}
+
+/// Where a lane's sanity cap comes from
+#[derive(Clone, Copy)]
+enum LaneSanity {
+    /// Precomputed per height
+    Curve(usize),
+    /// Follows M_L, computed each block
+    TiedToMl,
+    /// Not a term of M_B_max under the lane's rules
+    Unused,
+}
+
+/// One vector (a per-height curve or a window's starting contents) per
+/// distinct setting: the vectors and each lane's index
+fn shared_curves(
+    configs: &[SimulationConfig],
+    same: impl Fn(&SimulationConfig, &SimulationConfig) -> bool,
+    build: impl Fn(&SimulationConfig) -> Vec<i64>,
+) -> (Vec<Vec<i64>>, Vec<usize>) {
+    let mut curves = Vec::new();
+    let mut owners: Vec<usize> = Vec::new();
+    let mut index = Vec::with_capacity(configs.len());
+    for (k, config) in configs.iter().enumerate() {
+        match owners.iter().position(|&owner| same(&configs[owner], config)) {
+            Some(curve) => index.push(curve),
+            None => {
+                index.push(curves.len());
+                curves.push(build(config));
+                owners.push(k);
+            }
+        }
+    }
+    (curves, index)
+}
+
+/// Curves and window starts for one group of runs sharing a `LaneShape`,
+/// each run's index kept by its position in the group
+struct GroupSetup {
+    broadcast: Vec<Vec<i64>>,
+    broadcast_of: Vec<usize>,
+    sanity: Vec<Vec<i64>>,
+    sanity_of: Vec<LaneSanity>,
+    long: WindowStarts,
+    short: WindowStarts,
+}
+
+/// Distinct starting contents of a window, oldest first, with their medians
+struct WindowStarts {
+    values: Vec<Vec<i64>>,
+    medians: Vec<i64>,
+    of: Vec<usize>,
+}
+
+impl GroupSetup {
+    fn new(shape: LaneShape, configs: &[SimulationConfig], inputs: &SimulationInputs) -> GroupSetup {
+        let n = shape.n as usize;
+
+        // Transactions broadcast per height from exogenous demand; run type 5
+        // floods at M_B_max instead
+        let demand_trace = inputs.demand_trace.as_deref().filter(|trace| !trace.is_empty());
+        let (broadcast, broadcast_of) = shared_curves(
+            configs,
+            |a, b| {
+                a.t_sim == b.t_sim
+                    && (demand_trace.is_some()
+                        || (a.run_type == b.run_type
+                            && a.z_m == b.z_m
+                            && a.ramp_multiplier == b.ramp_multiplier
+                            && a.block_time == b.block_time))
+            },
+            |config| {
+                if demand_trace.is_none() && config.run_type == 5 {
+                    return Vec::new();
+                }
+                let block_time = config.block_time.max(1) as f64;
+                let mut elapsed: f64 = 0.0;
+                (0..n)
+                    .map(|i| {
+                        let t_ref = config.reference_blocks(elapsed / block_time);
+                        elapsed += block_time;
+                        let vol = match demand_trace {
+                            Some(trace) => trace[i.min(trace.len() - 1)],
+                            None => scheduled_demand(config, t_ref, 0),
+                        };
+                        vol / config.t_sim
+                    })
+                    .collect()
+            },
+        );
+
+        // A_C per height for the schedules that only depend on time
+        let time_based = |c: &SimulationConfig| {
+            c.rule_set == RuleSet::Current && !matches!(c.sanity_schedule, SanitySchedule::TiedToMl { .. })
+        };
+        let (sanity, sanity_curve) = shared_curves(
+            configs,
+            |a, b| {
+                time_based(a) == time_based(b)
+                    && a.sanity_schedule == b.sanity_schedule
+                    && a.sanity_start_block == b.sanity_start_block
+                    && a.sanity_start_weight == b.sanity_start_weight
+                    && a.sanity_growth_rate == b.sanity_growth_rate
+                    && a.block_time == b.block_time
+            },
+            |config| {
+                if time_based(config) {
+                    (0..n).map(|i| config.sanity_schedule.cap(config, i, 0)).collect()
+                } else {
+                    Vec::new()
+                }
+            },
+        );
+        let sanity_of = configs
+            .iter()
+            .zip(sanity_curve)
+            .map(|(config, curve)| match config.rule_set {
+                RuleSet::Legacy => LaneSanity::Unused,
+                RuleSet::Current if time_based(config) => LaneSanity::Curve(curve),
+                RuleSet::Current => LaneSanity::TiedToMl,
+            })
+            .collect();
+
+        // Windows start from the seed or flat at each run's steady state
+        let window_seed = inputs.window_seed.as_ref();
+        let starts = |weights: Option<&Vec<i64>>, len: usize| {
+            let (values, of) = shared_curves(
+                configs,
+                |a, b| a.steady_state == b.steady_state,
+                |config| match weights {
+                    Some(weights) => WindowSeed::fit(weights, len, config.steady_state),
+                    None => vec![config.steady_state; len],
+                },
+            );
+            let medians = values.iter().map(|values| median_of_unsorted(&mut values.clone())).collect();
+            WindowStarts { values, medians, of }
+        };
+        GroupSetup {
+            broadcast,
+            broadcast_of,
+            sanity,
+            sanity_of,
+            long: starts(window_seed.map(|s| &s.long_weights), shape.long_window),
+            short: starts(window_seed.map(|s| &s.short_weights), shape.short_window),
+        }
+    }
+}
+
+/// Run one chunk of a group's lanes, the runs from position `start` on
+fn run_lanes(
+    shape: LaneShape,
+    configs: &[SimulationConfig],
+    setup: &GroupSetup,
+    start: usize,
+    inputs: &SimulationInputs,
+    target_size: i64,
+) -> Vec<RunTotals> {
+    // Every chunk runs all `LANES` lanes; a short one is padded with copies
+    // of its first run, whose results are dropped
+    let runs = configs.len();
+    let configs: [SimulationConfig; LANES] = std::array::from_fn(|k| configs.get(k).copied().unwrap_or(configs[0]));
+    let configs = &configs;
+    let member: [usize; LANES] = std::array::from_fn(|k| start + if k < runs { k } else { 0 });
+    let n = shape.n as usize;
+    let window_seed = inputs.window_seed.as_ref();
+
+    let demand_trace = inputs.demand_trace.as_deref().filter(|trace| !trace.is_empty());
+    let flood: [bool; LANES] = std::array::from_fn(|k| demand_trace.is_none() && configs[k].run_type == 5);
+    let broadcast_curve = member.map(|m| &setup.broadcast[setup.broadcast_of[m]]);
+    let mut sanity_curves: Vec<(usize, &Vec<i64>)> = Vec::new();
+    let mut tied_to_ml: Vec<usize> = Vec::new();
+    for (k, &m) in member.iter().enumerate() {
+        match setup.sanity_of[m] {
+            LaneSanity::Curve(curve) => sanity_curves.push((k, &setup.sanity[curve])),
+            LaneSanity::TiedToMl => tied_to_ml.push(k),
+            LaneSanity::Unused => {}
+        }
+    }
+
+    let interval = |len: usize, floor: usize| (!shape.exact_median).then(|| (len / 2).max(floor));
+    let mut long = LaneWindows::new(interval(shape.long_window, 1000), n, &setup.long, member);
+    let mut short = LaneWindows::new(interval(shape.short_window, 10), n, &setup.short, member);
+
+    // Lane state
+    let m_l_prev: [i64; LANES] = match window_seed {
+        Some(_) => std::array::from_fn(|k| long.initial[k]),
+        None => configs.map(|c| c.steady_state),
+    };
+    let mut m_b = [window_seed.map_or(0, |s| s.last_block_weight); LANES];
+    let mut m_l = [0i64; LANES];
+    let mut m_s = [0i64; LANES];
+    let mut m_l_weight = [0i64; LANES];
+    let mut m_s_weight = [0i64; LANES];
+    let mut m_n = [0i64; LANES];
+    let mut rules_max = [0i64; LANES];
+    let mut broadcast = [0i64; LANES];
+    let mut pool_high = [0i64; LANES];
+    let mut pool_low = [0i64; LANES];
+    let mut rng: [Rng; LANES] = std::array::from_fn(|k| Rng::new(configs[k].stream_seed(12345)));
+    let noisy: Vec<usize> = (0..LANES).filter(|&k| configs[k].add_noise).collect();
+    let z_m = configs.map(|c| c.z_m);
+    let t_sim = configs.map(|c| c.t_sim);
+    let r_base = configs.map(|c| c.r_base);
+    let per_tx = configs.map(|c| Divisor::new(c.t_sim));
+
+    // Bounds on the M_L window weight, from the previous block's M_L
+    let ml_bounds = |m_l: &[i64; LANES]| {
+        let upper: [i64; LANES] = std::array::from_fn(|k| (configs[k].ml_mult * m_l[k] as f64) as i64);
+        let lower: [i64; LANES] = std::array::from_fn(|k| (m_l[k] as f64 / configs[k].ml_mult) as i64);
+        (upper, lower)
+    };
+    let (mut ml_upper, mut ml_lower) = ml_bounds(&m_l_prev);
+
+    let mut cumulative_fees = [0.0; LANES];
+    let mut max_mb = [0i64; LANES];
+    let mut max_penalty = [0.0; LANES];
+    let mut max_mempool = [0i64; LANES];
+    // First height at or above the target, u32::MAX until reached
+    let mut target_reached_at = [u32::MAX; LANES];
+
+    for i in 0..n {
+        let long_changed = long.medians(i, &mut m_l);
+        let short_changed = short.medians(i, &mut m_s);
+
+        // M_N and M_B_max under each lane's rules; only the sanity cap moves
+        // between median refreshes
+        if long_changed || short_changed {
+            for k in 0..LANES {
+                let config = &configs[k];
+                m_n[k] = config.rule_set.penalty_median(config, m_s[k], m_l[k]);
+                rules_max[k] = config.rule_set.max_block_weight(config, m_n[k], m_l[k], i64::MAX);
+            }
+        }
+        let mut m_b_max = rules_max;
+        for &(k, curve) in &sanity_curves {
+            m_b_max[k] = m_b_max[k].min(curve[i]);
+        }
+        for &k in &tied_to_ml {
+            let config = &configs[k];
+            m_b_max[k] = m_b_max[k].min(config.sanity_schedule.cap(config, i, m_l[k]));
+        }
+
+        // Window weights and new transactions
+        for k in 0..LANES {
+            m_l_weight[k] = m_b[k].min(ml_upper[k]).max(z_m[k]).max(ml_lower[k]);
+            m_s_weight[k] = m_b[k].max(m_l[k]);
+            broadcast[k] = if flood[k] { per_tx[k].div(m_b_max[k]) } else { broadcast_curve[k][i] };
+        }
+        for &k in &noisy {
+            if broadcast[k] > 0 {
+                let noise = 0.2 * rng[k].normal(0.0, broadcast[k] as f64);
+                broadcast[k] = (broadcast[k] + noise as i64).max(1);
+            }
+        }
+
+        // Fill each block from the mempool, high fee tier first
+        for k in 0..LANES {
+            let t_sim = t_sim[k];
+            pool_low[k] += broadcast[k];
+
+            let block = m_b_max[k].min((pool_high[k] + pool_low[k]) * t_sim);
+            let b = (block as f64 / m_n[k] as f64) - 1.0;
+            let penalty = if b > 0.0 { r_base[k] * b * b } else { 0.0 };
+            let mut tx_to_remove = per_tx[k].div(block + t_sim - 1); // ceil division
+            let remove_from_high = pool_high[k].min(tx_to_remove);
+            pool_high[k] -= remove_from_high;
+            tx_to_remove -= remove_from_high;
+            pool_low[k] = (pool_low[k] - tx_to_remove).max(0);
+
+            cumulative_fees[k] += penalty;
+            max_mempool[k] = max_mempool[k].max((pool_high[k] + pool_low[k]) * t_sim);
+            max_mb[k] = max_mb[k].max(block);
+            if penalty > max_penalty[k] {
+                max_penalty[k] = penalty;
+            }
+            let reached = if block >= target_size { i as u32 } else { u32::MAX };
+            target_reached_at[k] = target_reached_at[k].min(reached);
+            m_b[k] = block;
+        }
+
+        long.push(&m_l_weight);
+        short.push(&m_s_weight);
+        if long_changed {
+            (ml_upper, ml_lower) = ml_bounds(&m_l);
+        }
+    }
+
+    (0..runs)
+        .map(|k| RunTotals {
+            blocks: n as u32,
+            max_mb: max_mb[k],
+            max_penalty: max_penalty[k],
+            max_mempool: max_mempool[k],
+            cumulative_fees: cumulative_fees[k],
+            final_mb: if n > 0 { m_b[k] } else { 0 },
+            target_reached_at: (target_reached_at[k] != u32::MAX).then_some(target_reached_at[k]),
+        })
+        .collect()
+}
+
+/// Division by a run's constant transaction size. Integer division is the
+/// slowest step of a block, so the quotient comes from a float reciprocal,
+/// corrected to the exact `a / d` (off by at most one below 2^52).
+#[derive(Clone, Copy)]
+struct Divisor {
+    d: i64,
+    inverse: f64,
+}
+
+impl Divisor {
+    fn new(d: i64) -> Divisor {
+        Divisor { d, inverse: 1.0 / d as f64 }
+    }
+
+    fn div(self, a: i64) -> i64 {
+        if !(0..1 << 52).contains(&a) {
+            return a / self.d;
+        }
+        let q = (a as f64 * self.inverse) as i64;
+        let r = a - q * self.d;
+        q + (r >= self.d) as i64 - (r < 0) as i64
+    }
+}
+
+/// One median window per lane. Without `exact_median` the scalar engine
+/// re-sorts a window every `interval` pushes and reads its median from that
+/// copy in between, so medians are only refreshed on those blocks here and a
+/// window that is never re-read after the first block isn't kept at all.
+/// Kept windows share one buffer and one head position.
+struct LaneWindows {
+    /// Medians of the starting contents
+    initial: Vec<i64>,
+    kept: KeptWindows,
+}
+
+enum KeptWindows {
+    None,
+    Periodic {
+        ring: Vec<i64>,
+        len: usize,
+        head: usize,
+        interval: usize,
+        scratch: Vec<i64>,
+    },
+    Exact(Vec<MedianWindow>),
+}
+
+impl LaneWindows {
+    /// Windows for a run of `n` blocks, each lane starting from the contents
+    /// of the group position in `member`; a None `interval` keeps them exact
+    fn new(interval: Option<usize>, n: usize, starts: &WindowStarts, member: [usize; LANES]) -> LaneWindows {
+        let start_of = member.map(|m| starts.of[m]);
+        let initial = start_of.iter().map(|&start| starts.medians[start]).collect();
+        let kept = match interval {
+            None => KeptWindows::Exact(
+                start_of.iter().map(|&start| MedianWindow::from_values(&starts.values[start])).collect(),
+            ),
+            Some(interval) if interval >= n => KeptWindows::None,
+            Some(interval) => {
+                let len = starts.values.first().map_or(0, Vec::len);
+                let ring = start_of.iter().flat_map(|&start| starts.values[start].iter().copied()).collect();
+                KeptWindows::Periodic { ring, len, head: 0, interval, scratch: Vec::with_capacity(len) }
+            }
+        };
+        LaneWindows { initial, kept }
+    }
+
+    /// Write the medians block `height` sees into `medians`, returning
+    /// whether they were refreshed
+    fn medians(&mut self, height: usize, medians: &mut [i64]) -> bool {
+        if height == 0 {
+            medians.copy_from_slice(&self.initial);
+            return true;
+        }
+        match &mut self.kept {
+            KeptWindows::None => false,
+            KeptWindows::Exact(windows) => {
+                for (median, window) in medians.iter_mut().zip(windows.iter()) {
+                    *median = window.median();
+                }
+                true
+            }
+            KeptWindows::Periodic { ring, len, head, interval, scratch } => {
+                if !height.is_multiple_of(*interval) {
+                    return false;
+                }
+                for (median, window) in medians.iter_mut().zip(ring.chunks_exact(*len)) {
+                    // Oldest first, so a steadily moving window is seen sorted
+                    scratch.clear();
+                    scratch.extend_from_slice(&window[*head..]);
+                    scratch.extend_from_slice(&window[..*head]);
+                    *median = median_of_unsorted(scratch);
+                }
+                true
+            }
+        }
+    }
+
+    /// Replace each lane's oldest entry
+    fn push(&mut self, values: &[i64]) {
+        match &mut self.kept {
+            KeptWindows::None => {}
+            KeptWindows::Exact(windows) => {
+                for (window, &value) in windows.iter_mut().zip(values) {
+                    window.push(value);
+                }
+            }
+            KeptWindows::Periodic { ring, len, head, .. } => {
+                for (window, &value) in ring.chunks_exact_mut(*len).zip(values) {
+                    window[*head] = value;
+                }
+                *head = (*head + 1) % *len;
+            }
+        }
+    }
+}
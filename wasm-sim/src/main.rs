@@ -1,41 +1,76 @@
 //! Standalone CLI version of blockchain simulator for testing
 
-mod lib_core;
+mod tui;
 
-use lib_core::{SimulationConfig, run_simulation_core};
-use std::time::Instant;
+use wasm_sim::batch::ensemble_configs;
+use wasm_sim::behavior::FeeBehaviorModel;
+use wasm_sim::chart::{write_svg_charts, ChartKind, ChartScales};
+use wasm_sim::compare::{compare_with_inputs, Comparison};
+use wasm_sim::constraints::{BlockLimit, MlClamp};
+use wasm_sim::equilibrium::Equilibrium;
+use wasm_sim::events::EventTimeline;
+use wasm_sim::fork::WindowTransition;
+use wasm_sim::history::ChainHistory;
+use wasm_sim::lib_core::{RuleSet, SimulationConfig, SimulationInputs, run_simulation_observed, run_simulation_with_inputs};
+use wasm_sim::lockstep::run_totals;
+use wasm_sim::resources::{CostProfile, NodeProfile, ResourceReport};
+use wasm_sim::sanity::SanitySchedule;
+use wasm_sim::seed::WindowSeed;
+use wasm_sim::optimizer::{optimize, Constraint, Goal, Objective, OptimizerResult, Scenario};
+use wasm_sim::sensitivity::{sobol_analysis, Factor, Metric, SensitivityReport, SENSITIVITY_METRICS};
+use wasm_sim::summary::RunSummary;
+use wasm_sim::events::Param;
+use std::time::{Duration, Instant};
+use tui::Dashboard;
 use std::env;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     
-    // Default configuration
-    let mut config = SimulationConfig {
-        n: 20000,
-        steady_state: 1000000,  // NEW: 1MB (was 300kB)
-        z_m: 1000000,  // NEW: 1MB (was 300kB)
-        t_r: 10000,  // NEW: 10kB (was 3kB)
-        r_base: 0.6,
-        mid_100k: 50000,
-        mid_100: 50,
-        t_sim: 800,
-        run_type: 6,
-        ramp_multiplier: 3.0,
-        ml_mult: 2.0,
-        mn_mult: 50.0,
-        add_noise: false,
-        users_pay_more: false,
-        simple_blocks: false,  // Use detailed mode for comparison
-        large_sim_mode: false,
-        exact_median: false,
-        max_blocksize: 10_000_000,  // 10 MB default
-        max_blocksize_growth_rate: 0.0,  // 0% growth by default
-        use_long_term_median_cap: true,  // Use traditional M_N cap by default
-        sanity_start_weight: 10_000_000,  // NEW: 10MB sanity start weight
-        sanity_start_block: 0,  // NEW: Start from block 0
+    // Default configuration; --python-reference starts from the original
+    // script's settings instead, wherever it appears, so other flags still apply
+    let mut config = if args.iter().any(|arg| arg == "--python-reference") {
+        SimulationConfig::python_reference()
+    } else {
+        SimulationConfig {
+            simple_blocks: false,  // Use detailed mode for comparison
+            ..SimulationConfig::default()
+        }
     };
     
     let mut json_output = false;
+    let mut n_given = false;
+    let mut history_path: Option<String> = None;
+    let mut seed_history_path: Option<String> = None;
+    let mut seed_ramp_to: Option<i64> = None;
+    let mut seed_ramp_blocks: usize = 720;
+    let mut seed_windows_path: Option<String> = None;
+    let mut save_windows_path: Option<String> = None;
+    let mut node_report = false;
+    let mut events_path: Option<String> = None;
+    let mut node = NodeProfile::default();
+    let mut costs = CostProfile::default();
+    let mut fee_behavior: u32 = 0;
+    let mut behavior_params: [f64; 2] = [-1.0, -1.0];  // Negative: model default
+    let mut sanity_schedule = String::from("compound");
+    let mut sanity_ml_multiple: f64 = 16.0;
+    let mut equilibrium_demand: Option<i64> = None;
+    let mut sensitivity_samples: usize = 0;
+    let mut sensitivity_factors: Option<String> = None;
+    let mut target_size: Option<i64> = None;
+    let mut optimize_factors: Option<String> = None;
+    let mut objective_spec = String::from("min:max_penalty");
+    let mut constraint_specs: Vec<String> = Vec::new();
+    let mut scenario_specs: Vec<String> = Vec::new();
+    let mut max_evaluations: usize = 100;
+    let mut compare_overrides: Option<String> = None;
+    let mut compare_rule_set: Option<RuleSet> = None;
+    let mut charts_dir: Option<String> = None;
+    let mut tui = false;
+    let mut ensemble_runs: usize = 0;
+    let mut threads: usize = 0;
+    let mut chart_scales = ChartScales::default();
+    let mut chart_log: Vec<ChartKind> = Vec::new();
     
     // Parse command line arguments
     let mut i = 1;
@@ -44,9 +79,11 @@ fn main() {
             "--n" => {
                 if i + 1 < args.len() {
                     config.n = args[i + 1].parse().unwrap_or(20000);
+                    n_given = true;
                     i += 1;
                 }
             }
+            "--python-reference" => {}
             "--run-type" => {
                 if i + 1 < args.len() {
                     config.run_type = args[i + 1].parse().unwrap_or(6);
@@ -59,6 +96,18 @@ fn main() {
                     i += 1;
                 }
             }
+            "--add-noise" => {
+                if i + 1 < args.len() {
+                    config.add_noise = args[i + 1].parse().unwrap_or(0) != 0;
+                    i += 1;
+                }
+            }
+            "--simple-blocks" => {
+                if i + 1 < args.len() {
+                    config.simple_blocks = args[i + 1].parse().unwrap_or(0) != 0;
+                    i += 1;
+                }
+            }
             "--exact-median" => {
                 if i + 1 < args.len() {
                     config.exact_median = args[i + 1].parse().unwrap_or(0) != 0;
@@ -83,9 +132,391 @@ fn main() {
                     i += 1;
                 }
             }
+            "--demand-elasticity" => {
+                if i + 1 < args.len() {
+                    config.demand_elasticity = args[i + 1].parse().unwrap_or(0.0);
+                    i += 1;
+                }
+            }
+            "--delay-elasticity" => {
+                if i + 1 < args.len() {
+                    config.delay_elasticity = args[i + 1].parse().unwrap_or(0.0);
+                    i += 1;
+                }
+            }
+            "--users-pay-more" => {
+                if i + 1 < args.len() {
+                    config.users_pay_more = args[i + 1].parse().unwrap_or(0) != 0;
+                    i += 1;
+                }
+            }
+            "--fee-behavior" => {
+                if i + 1 < args.len() {
+                    fee_behavior = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--behavior-param-a" => {
+                if i + 1 < args.len() {
+                    behavior_params[0] = args[i + 1].parse().unwrap_or(-1.0);
+                    i += 1;
+                }
+            }
+            "--behavior-param-b" => {
+                if i + 1 < args.len() {
+                    behavior_params[1] = args[i + 1].parse().unwrap_or(-1.0);
+                    i += 1;
+                }
+            }
+            "--history" => {
+                if i + 1 < args.len() {
+                    history_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--seed-history" => {
+                if i + 1 < args.len() {
+                    seed_history_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--seed-ramp" => {
+                if i + 1 < args.len() {
+                    seed_ramp_to = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--seed-ramp-blocks" => {
+                if i + 1 < args.len() {
+                    seed_ramp_blocks = args[i + 1].parse().unwrap_or(720);
+                    i += 1;
+                }
+            }
+            "--seed-windows" => {
+                if i + 1 < args.len() {
+                    seed_windows_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--save-windows" => {
+                if i + 1 < args.len() {
+                    save_windows_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--block-time" => {
+                if i + 1 < args.len() {
+                    config.block_time = args[i + 1].parse().unwrap_or(120);
+                    i += 1;
+                }
+            }
+            "--long-window" => {
+                if i + 1 < args.len() {
+                    config.long_window = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--short-window" => {
+                if i + 1 < args.len() {
+                    config.short_window = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--miners" => {
+                if i + 1 < args.len() {
+                    config.miners = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--largest-miner-share" => {
+                if i + 1 < args.len() {
+                    config.largest_miner_share = args[i + 1].parse().unwrap_or(0.3);
+                    i += 1;
+                }
+            }
+            "--miner-bandwidth" => {
+                if i + 1 < args.len() {
+                    config.miner_bandwidth = args[i + 1].parse().unwrap_or(12_500_000.0);
+                    i += 1;
+                }
+            }
+            "--propagation-latency" => {
+                if i + 1 < args.len() {
+                    config.propagation_latency = args[i + 1].parse().unwrap_or(0.5);
+                    i += 1;
+                }
+            }
+            "--validation-rate" => {
+                if i + 1 < args.len() {
+                    config.validation_rate = args[i + 1].parse().unwrap_or(2_000_000.0);
+                    i += 1;
+                }
+            }
+            "--node-report" => {
+                node_report = true;
+            }
+            "--node-bandwidth" => {
+                if i + 1 < args.len() {
+                    node.bandwidth = args[i + 1].parse().unwrap_or(2_500_000.0);
+                    i += 1;
+                }
+            }
+            "--node-storage" => {
+                if i + 1 < args.len() {
+                    node.storage = args[i + 1].parse().unwrap_or(1e12);
+                    i += 1;
+                }
+            }
+            "--node-cores" => {
+                if i + 1 < args.len() {
+                    node.cores = args[i + 1].parse().unwrap_or(4.0);
+                    i += 1;
+                }
+            }
+            "--bytes-per-input" => {
+                if i + 1 < args.len() {
+                    costs.bytes_per_input = args[i + 1].parse().unwrap_or(1_000.0);
+                    i += 1;
+                }
+            }
+            "--verify-seconds-per-input" => {
+                if i + 1 < args.len() {
+                    costs.seconds_per_input = args[i + 1].parse().unwrap_or(0.0015);
+                    i += 1;
+                }
+            }
+            "--verify-seconds-per-byte" => {
+                if i + 1 < args.len() {
+                    costs.seconds_per_byte = args[i + 1].parse().unwrap_or(0.000_000_25);
+                    i += 1;
+                }
+            }
+            "--relay-factor" => {
+                if i + 1 < args.len() {
+                    costs.relay_factor = args[i + 1].parse().unwrap_or(4.0);
+                    i += 1;
+                }
+            }
+            "--initial-chain-size" => {
+                if i + 1 < args.len() {
+                    node.initial_chain_size = args[i + 1].parse().unwrap_or(0.0);
+                    i += 1;
+                }
+            }
+            "--stochastic-intervals" => {
+                if i + 1 < args.len() {
+                    config.stochastic_intervals = args[i + 1].parse().unwrap_or(0) != 0;
+                    i += 1;
+                }
+            }
+            "--rule-set" => {
+                if i + 1 < args.len() {
+                    config.rule_set = RuleSet::parse(&args[i + 1]).unwrap_or(RuleSet::Current);
+                    i += 1;
+                }
+            }
+            "--fork-height" => {
+                if i + 1 < args.len() {
+                    config.fork_height = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--fork-rule-set" => {
+                if i + 1 < args.len() {
+                    config.fork_rule_set = RuleSet::parse(&args[i + 1]).unwrap_or(RuleSet::Current);
+                    i += 1;
+                }
+            }
+            "--fork-windows" => {
+                if i + 1 < args.len() {
+                    config.fork_windows = WindowTransition::parse(&args[i + 1]).unwrap_or(WindowTransition::Carry);
+                    i += 1;
+                }
+            }
+            "--sanity-start-weight" => {
+                if i + 1 < args.len() {
+                    config.sanity_start_weight = args[i + 1].parse().unwrap_or(10_000_000);
+                    i += 1;
+                }
+            }
+            "--sanity-start-block" => {
+                if i + 1 < args.len() {
+                    config.sanity_start_block = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--sanity-schedule" => {
+                if i + 1 < args.len() {
+                    sanity_schedule = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--sanity-growth-rate" => {
+                if i + 1 < args.len() {
+                    config.sanity_growth_rate = args[i + 1].parse().unwrap_or(config.sanity_growth_rate);
+                    i += 1;
+                }
+            }
+            "--sanity-ml-multiple" => {
+                if i + 1 < args.len() {
+                    sanity_ml_multiple = args[i + 1].parse().unwrap_or(16.0);
+                    i += 1;
+                }
+            }
+            "--full-trace" => {
+                if i + 1 < args.len() {
+                    config.full_trace = args[i + 1].parse().unwrap_or(0) != 0;
+                    i += 1;
+                }
+            }
+            "--equilibrium" => {
+                if i + 1 < args.len() {
+                    equilibrium_demand = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--sensitivity" => {
+                if i + 1 < args.len() {
+                    sensitivity_samples = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--sensitivity-factors" => {
+                if i + 1 < args.len() {
+                    sensitivity_factors = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--target-size" => {
+                if i + 1 < args.len() {
+                    target_size = args[i + 1].parse().ok();
+                    i += 1;
+                }
+            }
+            "--ml-cap-mult" => {
+                if i + 1 < args.len() {
+                    config.ml_cap_mult = args[i + 1].parse().unwrap_or(16.0);
+                    i += 1;
+                }
+            }
+            "--optimize" => {
+                if i + 1 < args.len() {
+                    optimize_factors = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--objective" => {
+                if i + 1 < args.len() {
+                    objective_spec = args[i + 1].clone();
+                    i += 1;
+                }
+            }
+            "--constraint" => {
+                if i + 1 < args.len() {
+                    constraint_specs.push(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--scenario" => {
+                if i + 1 < args.len() {
+                    scenario_specs.push(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--max-evaluations" => {
+                if i + 1 < args.len() {
+                    max_evaluations = args[i + 1].parse().unwrap_or(100);
+                    i += 1;
+                }
+            }
+            "--compare" => {
+                if i + 1 < args.len() {
+                    compare_overrides = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--compare-rule-set" => {
+                if i + 1 < args.len() {
+                    compare_rule_set = RuleSet::parse(&args[i + 1]);
+                    i += 1;
+                }
+            }
+            "--events" => {
+                if i + 1 < args.len() {
+                    events_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
             "--json" => {
                 json_output = true;
             }
+            "--tui" => {
+                tui = true;
+            }
+            "--seed" => {
+                if i + 1 < args.len() {
+                    config.seed = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--ensemble" => {
+                if i + 1 < args.len() {
+                    ensemble_runs = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--threads" => {
+                if i + 1 < args.len() {
+                    threads = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+            }
+            "--charts" => {
+                if i + 1 < args.len() {
+                    charts_dir = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--chart-log" => {
+                if i + 1 < args.len() {
+                    chart_log = if args[i + 1] == "all" {
+                        ChartKind::ALL.to_vec()
+                    } else {
+                        args[i + 1].split(',').filter_map(|name| ChartKind::parse(name.trim())).collect()
+                    };
+                    i += 1;
+                }
+            }
+            "--scale-blocksize" => {
+                if i + 1 < args.len() {
+                    chart_scales.block_size = args[i + 1].parse().unwrap_or(1.0);
+                    i += 1;
+                }
+            }
+            "--scale-txpool" => {
+                if i + 1 < args.len() {
+                    chart_scales.txpool = args[i + 1].parse().unwrap_or(1.0);
+                    i += 1;
+                }
+            }
+            "--scale-volume" => {
+                if i + 1 < args.len() {
+                    chart_scales.volume = args[i + 1].parse().unwrap_or(1.0);
+                    i += 1;
+                }
+            }
+            "--scale-fees" => {
+                if i + 1 < args.len() {
+                    chart_scales.fees = args[i + 1].parse().unwrap_or(1.0);
+                    i += 1;
+                }
+            }
+            "--scale-penalty" => {
+                if i + 1 < args.len() {
+                    chart_scales.penalty = args[i + 1].parse().unwrap_or(1000.0);
+                    i += 1;
+                }
+            }
             _ => {
                 eprintln!("Unknown argument: {}", args[i]);
                 std::process::exit(1);
@@ -94,10 +525,197 @@ fn main() {
         i += 1;
     }
     
+    // The dashboard draws on the terminal the JSON goes to
+    if tui && json_output {
+        eprintln!("--tui cannot be combined with --json");
+        std::process::exit(1);
+    }
+    
+    config.fee_behavior = FeeBehaviorModel::from_parts(fee_behavior, behavior_params[0], behavior_params[1]);
+    config.sanity_schedule = SanitySchedule::parse(&sanity_schedule, sanity_ml_multiple).unwrap_or(SanitySchedule::Compound);
+    
+    // Replay recorded chain history as demand
+    let mut inputs = SimulationInputs::default();
+    let history = history_path.map(|path| match ChainHistory::load(&path) {
+        Ok(history) => history,
+        Err(err) => {
+            eprintln!("Failed to load history: {}", err);
+            std::process::exit(1);
+        }
+    });
+    if let Some(history) = &history {
+        if !n_given {
+            config.n = history.len() as u32;
+        }
+        inputs.demand_trace = Some(history.weights());
+    } else if let Some(demand) = equilibrium_demand {
+        // Constant demand, so the run can be checked against the analytic equilibrium
+        inputs.demand_trace = Some(vec![demand]);
+    }
+    
+    // Scripted scenario events
+    if let Some(path) = &events_path {
+        match EventTimeline::load(path) {
+            Ok(timeline) => inputs.events = Some(timeline),
+            Err(err) => {
+                eprintln!("Failed to load events: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    // Seed the median windows from a previous run's dump, history or a ramp
+    // ending at the given weight
+    if let Some(path) = &seed_windows_path {
+        match WindowSeed::load(path) {
+            Ok(seed) => inputs.window_seed = Some(seed),
+            Err(err) => {
+                eprintln!("Failed to load window dump: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(path) = &seed_history_path {
+        match ChainHistory::load(path) {
+            Ok(seed_history) => inputs.window_seed = Some(WindowSeed::from_history(&seed_history, &config)),
+            Err(err) => {
+                eprintln!("Failed to load seed history: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(to) = seed_ramp_to {
+        inputs.window_seed = Some(WindowSeed::ramp(config.steady_state, to, seed_ramp_blocks, &config));
+    }
+    
+    // Sensitivity study instead of a single run
+    if sensitivity_samples > 0 {
+        let factors: Vec<Factor> = match &sensitivity_factors {
+            Some(specs) => match specs.split(',').map(Factor::parse).collect() {
+                Ok(factors) => factors,
+                Err(err) => {
+                    eprintln!("Invalid sensitivity factor: {}", err);
+                    std::process::exit(1);
+                }
+            },
+            None => [Param::RBase, Param::TR, Param::ZM, Param::MlMult, Param::RampMultiplier, Param::SanityGrowthRate]
+                .into_iter()
+                .map(|param| Factor::around(param, &config, 0.5))
+                .collect(),
+        };
+        let target = target_size.unwrap_or(10 * config.steady_state);
+        let start = Instant::now();
+        let report = sobol_analysis(&config, &inputs, &factors, sensitivity_samples, target, config.seed, threads);
+        print_sensitivity(&report, target, json_output, start.elapsed().as_secs_f64());
+        return;
+    }
+    
+    // Search rule parameters for a goal instead of a single run
+    if let Some(specs) = &optimize_factors {
+        let fail = |err: String| -> ! {
+            eprintln!("Invalid optimizer setup: {}", err);
+            std::process::exit(1);
+        };
+        let factors: Vec<Factor> = specs.split(',').map(Factor::parse).collect::<Result<_, _>>().unwrap_or_else(|e| fail(e));
+        
+        // The run as configured is the "base" scenario; each --scenario name=events adds one
+        let mut scenarios = vec![Scenario { name: "base".to_string(), config, inputs: inputs.clone() }];
+        for spec in &scenario_specs {
+            let (name, path) = spec.split_once('=').unwrap_or_else(|| fail(format!("'{}': expected name=events_file", spec)));
+            let timeline = EventTimeline::load(path).unwrap_or_else(|e| fail(e));
+            let scenario_inputs = SimulationInputs { events: Some(timeline), ..inputs.clone() };
+            scenarios.push(Scenario { name: name.to_string(), config, inputs: scenario_inputs });
+        }
+        let goal = Goal {
+            objective: Objective::parse(&objective_spec, &scenarios).unwrap_or_else(|e| fail(e)),
+            constraints: constraint_specs
+                .iter()
+                .map(|spec| Constraint::parse(spec, &scenarios))
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|e| fail(e)),
+            target_size: target_size.unwrap_or(10 * config.steady_state),
+        };
+        let start = Instant::now();
+        let result = optimize(&scenarios, &factors, &goal, max_evaluations, threads).unwrap_or_else(|e| fail(e));
+        print_optimizer(&result, &scenarios, &goal, json_output, start.elapsed().as_secs_f64());
+        return;
+    }
+    
+    // A/B comparison: config B is this config with the given overrides
+    if compare_overrides.is_some() || compare_rule_set.is_some() {
+        let mut config_b = config;
+        if let Some(rules) = compare_rule_set {
+            config_b.rule_set = rules;
+        }
+        for spec in compare_overrides.iter().flat_map(|s| s.split(',')).filter(|s| !s.is_empty()) {
+            let parsed = spec
+                .split_once('=')
+                .and_then(|(name, value)| Some((Param::parse(name)?, value.parse::<f64>().ok()?)));
+            match parsed {
+                Some((param, value)) => param.apply(&mut config_b, value),
+                None => {
+                    eprintln!("Invalid comparison override '{}': expected name=value", spec);
+                    std::process::exit(1);
+                }
+            }
+        }
+        let thresholds: Vec<i64> = match target_size {
+            Some(size) => vec![size],
+            None => [2, 5, 10].iter().map(|k| k * config.steady_state).collect(),
+        };
+        let start = Instant::now();
+        let comparison = compare_with_inputs(config, config_b, &inputs, &thresholds);
+        print_comparison(&comparison, json_output, start.elapsed().as_secs_f64());
+        return;
+    }
+    
+    // Ensemble: the same config under different random seeds (noise, block
+    // intervals, miners), with --seed as the base seed
+    if ensemble_runs > 0 {
+        let target_size = target_size.unwrap_or(10 * config.steady_state);
+        let configs = ensemble_configs(&config, ensemble_runs, config.seed);
+        let start = Instant::now();
+        let outcomes: Vec<[f64; 6]> = run_totals(&configs, &inputs, threads, target_size)
+            .iter()
+            .map(|totals| Metric::ALL.map(|metric| metric.of_totals(totals)))
+            .collect();
+        print_ensemble(&outcomes, json_output, start.elapsed().as_secs_f64());
+        return;
+    }
+    
     // Run simulation
     let start = Instant::now();
-    let results = run_simulation_core(config);
-    let duration = start.elapsed();
+    let (results, duration) = if tui {
+        // Live dashboard; the usual report follows once it closes. Time spent
+        // drawing, throttling and paused doesn't count towards the run time.
+        let mut dashboard = Dashboard::new(config.n);
+        let mut dashboard_time = Duration::ZERO;
+        let results = run_simulation_observed(config, &inputs, |block| {
+            let frame = Instant::now();
+            let flow = dashboard.observe(block);
+            dashboard_time += frame.elapsed();
+            flow
+        });
+        dashboard.finish();
+        (results, start.elapsed().saturating_sub(dashboard_time))
+    } else {
+        (run_simulation_with_inputs(config, &inputs), start.elapsed())
+    };
+    let comparison = history.as_ref().map(|history| history.compare(&results));
+    let equilibrium = equilibrium_demand.map(|demand| Equilibrium::solve(&config, demand));
+    let summary = RunSummary::from_results(&results, &config);
+    let charts = charts_dir.map(|dir| match write_svg_charts(dir.as_ref(), &results, &chart_scales, &chart_log) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("Failed to write charts: {}", err);
+            std::process::exit(1);
+        }
+    });
+    if let Some(path) = &save_windows_path {
+        if let Err(err) = results.final_windows.save(path) {
+            eprintln!("Failed to save window dump: {}", err);
+            std::process::exit(1);
+        }
+    }
+    let resources = node_report.then(|| ResourceReport::from_results(&results, &config, &costs, &node));
     
     if json_output {
         // Output JSON for comparison script
@@ -111,6 +729,72 @@ fn main() {
         println!("  \"final_ms\": {},", results.M_S.last().unwrap_or(&0));
         println!("  \"final_mn\": {},", results.M_N.last().unwrap_or(&0));
         println!("  \"final_t_sim\": 800,");
+        println!("  \"sanity_binding_blocks\": {},", results.sanity_binding.len());
+        println!("  \"binding_constraints\": {{");
+        for limit in BlockLimit::ALL {
+            println!("    \"{}\": {},", limit.label(), results.constraints.blocks(limit));
+        }
+        let clamps: Vec<String> = MlClamp::ALL
+            .iter()
+            .map(|&clamp| format!("\"{}\": {}", clamp.label(), results.constraints.clamped(clamp)))
+            .collect();
+        println!("    \"ml_clamp\": {{{}}}", clamps.join(", "));
+        println!("  }},");
+        println!("  \"summary\": {{");
+        let times: Vec<String> = summary
+            .time_to_size
+            .iter()
+            .map(|t| format!("\"{}x\": {}", t.multiple, t.blocks.map_or("null".to_string(), |b| b.to_string())))
+            .collect();
+        println!("    \"time_to_steady_state_multiple\": {{{}}},", times.join(", "));
+        println!("    \"mempool_peak_at\": {},", summary.mempool_peak_at);
+        match summary.mempool_recovery {
+            Some(blocks) => println!("    \"mempool_recovery\": {},", blocks),
+            None => println!("    \"mempool_recovery\": null,"),
+        }
+        println!("    \"fee_spike_ratio\": {},", summary.fee_spike_ratio);
+        println!("    \"longest_full_span\": {},", summary.longest_full_span);
+        println!("    \"longest_full_span_start\": {},", summary.longest_full_span_start);
+        println!("    \"backlog_area\": {}", summary.backlog_area);
+        println!("  }},");
+        if let Some(report) = &resources {
+            println!("  \"node_resources\": {{");
+            println!("    \"final_chain_size\": {},", report.final_chain_size());
+            println!("    \"peak_bandwidth_per_day\": {},", report.peak_bandwidth_per_day());
+            println!("    \"peak_verify_seconds\": {},", report.peak_verify_seconds());
+            match report.falls_behind_at {
+                Some((block, bottleneck)) => println!("    \"falls_behind_at\": {{\"block\": {}, \"bottleneck\": \"{:?}\"}}", block, bottleneck),
+                None => println!("    \"falls_behind_at\": null"),
+            }
+            println!("  }},");
+        }
+        if let Some(eq) = &equilibrium {
+            println!("  \"equilibrium\": {{");
+            println!("    \"demand\": {},", eq.demand);
+            println!("    \"m_b\": {},", eq.m_b);
+            println!("    \"m_l\": {},", eq.m_l);
+            println!("    \"m_s\": {},", eq.m_s);
+            println!("    \"m_n\": {},", eq.m_n);
+            println!("    \"m_b_max\": {},", eq.m_b_max);
+            println!("    \"f_r\": {},", eq.f_r);
+            println!("    \"backlog_growth\": {},", eq.backlog_growth);
+            match eq.converge_blocks {
+                Some(blocks) => println!("    \"converge_blocks\": {}", blocks),
+                None => println!("    \"converge_blocks\": null"),
+            }
+            println!("  }},");
+        }
+        if let Some(cmp) = &comparison {
+            println!("  \"history_comparison\": {{");
+            println!("    \"blocks\": {},", cmp.blocks);
+            for (name, err, last) in [("M_B", &cmp.m_b, false), ("M_L", &cmp.m_l, false), ("M_S", &cmp.m_s, true)] {
+                println!(
+                    "    \"{}\": {{\"rmse\": {}, \"mean_abs_pct\": {}, \"max_abs\": {}}}{}",
+                    name, err.rmse, err.mean_abs_pct, err.max_abs, if last { "" } else { "," }
+                );
+            }
+            println!("  }},");
+        }
         println!("  \"sample_data\": {{");
         
         // Helper function to format array
@@ -133,10 +817,48 @@ fn main() {
         print!(",");
         format_array("input_volume", &results.input_volume);
         print!(",");
+        format_array("input_volume_high", &results.input_volume_high);
+        print!(",");
+        format_array("input_volume_low", &results.input_volume_low);
+        print!(",");
+        format_array("mempool_high", &results.mempool_high);
+        print!(",");
+        format_array("mempool_low", &results.mempool_low);
+        print!(",");
         format_array("block_fee", &results.block_fee);
         print!(",");
         format_array("penalty", &results.penalty);
         print!(",");
+        format_array("timestamp", &results.timestamps);
+        print!(",");
+        format_array("sanity_cap", &results.sanity_cap);
+        print!(",");
+        format_array("block_limit", &results.block_limit);
+        print!(",");
+        format_array("ml_clamp", &results.ml_clamp);
+        print!(",");
+        if let Some(trace) = &results.trace {
+            format_array("m_l_weight", &trace.m_l_weight);
+            print!(",");
+            format_array("m_s_weight", &trace.m_s_weight);
+            print!(",");
+            format_array("m_b_max", &trace.m_b_max);
+            print!(",");
+            format_array("f_r", &trace.f_r);
+            print!(",");
+            format_array("last_f_t", &trace.last_f_t);
+            print!(",");
+            format_array("mempool_high_tx", &trace.mempool_high);
+            print!(",");
+            format_array("mempool_low_tx", &trace.mempool_low);
+            print!(",");
+            format_array("blockfilled_tier", &trace.blockfilled_tier);
+            print!(",");
+            format_array("blockfilled_count", &trace.blockfilled_count);
+            print!(",");
+            format_array("percent_response", &trace.percent_response);
+            print!(",");
+        }
         print!("    \"t_sim\": [");
         for i in 0..results.indices.len() {
             if i > 0 { print!(", "); }
@@ -154,14 +876,34 @@ fn main() {
         println!("Configuration:");
         println!("  Blocks: {}", config.n);
         println!("  Run Type: {}", config.run_type);
+        println!("  Rule Set: {:?}", config.rule_set);
+        if let Some(timeline) = &inputs.events {
+            println!("  Scripted Events: {}", timeline.events.len());
+        }
+        println!("  Block Time: {}s{}", config.block_time, if config.stochastic_intervals { " (exponential intervals)" } else { "" });
+        println!("  Median Windows: {} / {} blocks", config.long_window_len(), config.short_window_len());
         println!("  Simple Blocks: {}", config.simple_blocks);
         println!("  Add Noise: {}", config.add_noise);
         println!("  Users Pay More: {}", config.users_pay_more);
+        println!("  Fee Behavior: {:?}", FeeBehaviorModel::resolve(&config));
         println!("  Large Sim Mode: {}", config.large_sim_mode);
         println!("  Exact Median: {}", config.exact_median);
         println!("  Max Blocksize: {} bytes", config.max_blocksize);
         println!("  Max Blocksize Growth Rate: {:.2}% per year", config.max_blocksize_growth_rate * 100.0);
         println!("  Use Long Term Median Cap: {}", config.use_long_term_median_cap);
+        println!(
+            "  Sanity Cap: {:?} from {} bytes at block {} ({:.3e} growth per 2-minute block)",
+            config.sanity_schedule, config.sanity_start_weight, config.sanity_start_block, config.sanity_growth_rate
+        );
+        if let Some(path) = &seed_windows_path {
+            println!("  Window Seed: dump {}", path);
+        } else if seed_history_path.is_some() || seed_ramp_to.is_some() {
+            println!("  Window Seed: {}", seed_history_path.as_deref().map_or_else(
+                || format!("ramp to {} bytes over {} blocks", seed_ramp_to.unwrap_or(0), seed_ramp_blocks),
+                |path| format!("history {}", path),
+            ));
+        }
+        println!("  Demand Elasticity: {} (delay: {})", config.demand_elasticity, config.delay_elasticity);
         println!();
         
         println!("Running simulation...");
@@ -173,5 +915,322 @@ fn main() {
         println!("  Max Penalty: {:.6}", results.max_penalty);
         println!("  Cumulative Fees: {:.6} XMR", results.cumulative_fees);
         println!("  Data Points: {}", results.data_points);
+        match results.sanity_binding.first() {
+            Some(first) => println!("  Sanity Cap Binding: {} blocks (first at block {})", results.sanity_binding.len(), first),
+            None => println!("  Sanity Cap Binding: never"),
+        }
+        
+        let blocks = results.constraints.block_limit.iter().sum::<u32>().max(1) as f64;
+        println!("\nBinding Constraints:");
+        for limit in BlockLimit::ALL {
+            let count = results.constraints.blocks(limit);
+            println!("  {:<7} {:>8} blocks ({:.1}%)", limit.label(), count, count as f64 * 100.0 / blocks);
+        }
+        println!("  M_L weight clamp:");
+        for clamp in MlClamp::ALL {
+            let count = results.constraints.clamped(clamp);
+            println!("    {:<5} {:>8} blocks ({:.1}%)", clamp.label(), count, count as f64 * 100.0 / blocks);
+        }
+        
+        println!("\nSummary:");
+        for t in &summary.time_to_size {
+            match t.blocks {
+                Some(blocks) => println!("  M_B reaches {}x steady state: block {}", t.multiple, blocks),
+                None => println!("  M_B reaches {}x steady state: never", t.multiple),
+            }
+        }
+        match summary.mempool_recovery {
+            Some(blocks) => println!("  Mempool peak at block {}, clears after {} blocks", summary.mempool_peak_at, blocks),
+            None => println!("  Mempool peak at block {}, not cleared by the end of the run", summary.mempool_peak_at),
+        }
+        println!("  Peak-to-median fee per byte: {:.2}x", summary.fee_spike_ratio);
+        if summary.longest_full_span > 0 {
+            println!(
+                "  Longest full-block span: {} blocks (from block {})",
+                summary.longest_full_span, summary.longest_full_span_start
+            );
+        } else {
+            println!("  Longest full-block span: none");
+        }
+        println!("  Backlog area: {:.3e} byte-blocks", summary.backlog_area);
+        
+        for fork in &results.forks {
+            println!("\nFork at block {}: {:?} -> {:?} ({:?} windows)", fork.height, fork.from, fork.to, fork.windows);
+            println!("  M_B_max: {} -> {} bytes", fork.m_b_max_before, fork.m_b_max_after);
+            println!("  Peak M_N deviation: {:.2}%", fork.peak_m_n_deviation * 100.0);
+            match fork.settle_blocks {
+                Some(blocks) => println!("  M_N settles after {} blocks", blocks),
+                None => println!("  M_N does not settle before the next fork/end of run"),
+            }
+        }
+        
+        if let Some(eq) = &equilibrium {
+            println!("\nEquilibrium (constant demand {} bytes/block):", eq.demand);
+            println!("  {:<8} {:>14} {:>14}", "", "analytic", "simulated");
+            let last = |series: &[i64]| series.last().copied().unwrap_or(0);
+            for (name, analytic, simulated) in [
+                ("M_B", eq.m_b, last(&results.M_B)),
+                ("M_L", eq.m_l, last(&results.M_L)),
+                ("M_S", eq.m_s, last(&results.M_S)),
+                ("M_N", eq.m_n, last(&results.M_N)),
+            ] {
+                println!("  {:<8} {:>14} {:>14}", name, analytic, simulated);
+            }
+            println!("  M_B_max: {} bytes", eq.m_b_max);
+            println!("  Fee: {:.6e} per byte (low tier)", eq.f_r);
+            if eq.backlog_growth > 0 {
+                println!("  Backlog grows by {} bytes/block", eq.backlog_growth);
+            }
+            match eq.converge_blocks {
+                Some(blocks) => println!("  Converges after ~{} blocks", blocks),
+                None => println!("  Does not converge within the solver horizon"),
+            }
+        }
+        
+        if let Some(report) = &resources {
+            println!("\nNode Resources:");
+            println!("  Final Chain Size: {:.2} GB", report.final_chain_size() / 1e9);
+            println!("  Peak Bandwidth: {:.2} GB/day", report.peak_bandwidth_per_day() / 1e9);
+            println!("  Peak Verification: {:.2} s/block", report.peak_verify_seconds());
+            match report.falls_behind_at {
+                Some((block, bottleneck)) => println!("  Reference node falls behind at block {} ({:?})", block, bottleneck),
+                None => println!("  Reference node keeps up"),
+            }
+        }
+        
+        if let Some(network) = &results.network {
+            let total_revenue = network.total_revenue();
+            println!("\nNetwork ({} miners):", network.miners.len());
+            println!("  Orphan Rate: {:.3}% ({} blocks)", network.orphan_rate() * 100.0, network.orphaned_blocks.len());
+            for (k, miner) in network.miners.iter().enumerate() {
+                let avg_size = if miner.blocks_mined > 0 { miner.bytes_mined / miner.blocks_mined as i64 } else { 0 };
+                println!(
+                    "  Miner {}: {:.1}% hashrate, {} blocks, avg {} bytes, {:.3}% orphaned, revenue/hashrate {:.4}",
+                    k, miner.hashrate_share * 100.0, miner.blocks_mined, avg_size,
+                    miner.orphan_rate() * 100.0, miner.revenue_ratio(total_revenue)
+                );
+            }
+        }
+        
+        if let Some(paths) = &charts {
+            println!("\nCharts:");
+            for path in paths {
+                println!("  {}", path.display());
+            }
+        }
+        if let Some(path) = &save_windows_path {
+            println!("\nFinal windows saved to {}", path);
+        }
+        
+        if let (Some(history), Some(cmp)) = (&history, &comparison) {
+            let total_tx: u64 = history.blocks.iter().map(|b| b.tx_count as u64).sum();
+            println!("\nHistory Replay vs mainnet medians ({} blocks from height {}, {} tx):", cmp.blocks, history.blocks[0].height, total_tx);
+            for (name, err) in [("M_B", &cmp.m_b), ("M_L", &cmp.m_l), ("M_S", &cmp.m_s)] {
+                println!("  {}: RMSE {:.0} bytes, mean abs error {:.2}%, max abs error {} bytes", name, err.rmse, err.mean_abs_pct, err.max_abs);
+            }
+        }
+    }
+}
+
+fn print_sensitivity(report: &SensitivityReport, target_size: i64, json_output: bool, seconds: f64) {
+    if json_output {
+        println!("{{");
+        println!("  \"runs\": {},", report.runs);
+        println!("  \"target_size\": {},", target_size);
+        println!("  \"factors\": [");
+        for (k, factor) in report.factors.iter().enumerate() {
+            let indices: Vec<String> = SENSITIVITY_METRICS
+                .iter()
+                .map(|&metric| {
+                    let index = report.index(k, metric);
+                    format!("\"{}\": {{\"first_order\": {}, \"total_order\": {}}}", metric.label(), index.first_order, index.total_order)
+                })
+                .collect();
+            println!(
+                "    {{\"param\": \"{}\", \"low\": {}, \"high\": {}, {}}}{}",
+                factor.param.name(), factor.low, factor.high, indices.join(", "),
+                if k + 1 < report.factors.len() { "," } else { "" }
+            );
+        }
+        println!("  ]");
+        println!("}}");
+        return;
+    }
+    
+    println!("Sensitivity Analysis (Sobol indices, {} runs in {:.1}s)", report.runs, seconds);
+    println!("==================================================\n");
+    println!("Time to target: blocks until M_B reaches {} bytes\n", target_size);
+    for (m, metric) in SENSITIVITY_METRICS.iter().enumerate() {
+        println!("{} (variance {:.4e}):", metric.label(), report.variance[m]);
+        println!("  {:<20} {:>26} {:>8} {:>8}", "parameter", "range", "S1", "ST");
+        for (k, factor) in report.factors.iter().enumerate() {
+            let index = report.index(k, *metric);
+            println!(
+                "  {:<20} {:>12.4e} - {:<11.4e} {:>8.3} {:>8.3}",
+                factor.param.name(), factor.low, factor.high, index.first_order, index.total_order
+            );
+        }
+        println!();
+    }
+}
+
+fn print_optimizer(result: &OptimizerResult, scenarios: &[Scenario], goal: &Goal, json_output: bool, seconds: f64) {
+    let best = &result.best;
+    if json_output {
+        println!("{{");
+        println!("  \"evaluations\": {},", result.evaluations);
+        println!("  \"objective\": {},", best.objective);
+        println!("  \"violation\": {},", best.violation);
+        println!("  \"feasible\": {},", best.feasible());
+        let params: Vec<String> = result
+            .factors
+            .iter()
+            .zip(&best.values)
+            .map(|(factor, value)| format!("\"{}\": {}", factor.param.name(), value))
+            .collect();
+        println!("  \"best\": {{{}}},", params.join(", "));
+        let history: Vec<String> = result
+            .history
+            .iter()
+            .map(|c| format!("{{\"objective\": {}, \"violation\": {}}}", c.objective, c.violation))
+            .collect();
+        println!("  \"history\": [{}],", history.join(", "));
+        println!("  \"scenarios\": [");
+        for (k, (scenario, results)) in scenarios.iter().zip(&result.best_results).enumerate() {
+            let metrics: Vec<String> = Metric::ALL
+                .iter()
+                .map(|&metric| format!("\"{}\": {}", metric.label(), metric.measure(results, goal.target_size)))
+                .collect();
+            let m_b: Vec<String> = results.M_B.iter().map(|v| v.to_string()).collect();
+            println!(
+                "    {{\"name\": \"{}\", {}, \"M_B\": [{}]}}{}",
+                scenario.name, metrics.join(", "), m_b.join(", "),
+                if k + 1 < scenarios.len() { "," } else { "" }
+            );
+        }
+        println!("  ]");
+        println!("}}");
+        return;
+    }
+    
+    println!("Rule Parameter Search (Nelder-Mead, {} evaluations in {:.1}s)", result.evaluations, seconds);
+    println!("==================================================\n");
+    println!("Best configuration ({}):", if best.feasible() { "meets all constraints" } else { "constraints NOT met" });
+    for (factor, value) in result.factors.iter().zip(&best.values) {
+        println!("  {:<20} {:.6e}  (range {:.4e} - {:.4e})", factor.param.name(), value, factor.low, factor.high);
+    }
+    println!("  Objective: {:.6e}", best.objective);
+    if !best.feasible() {
+        println!("  Total constraint violation: {:.4}", best.violation);
+    }
+    
+    println!("\nSearch trajectory (best per iteration):");
+    let stride = result.history.len().div_ceil(20).max(1);
+    for (k, candidate) in result.history.iter().enumerate().filter(|(k, _)| k % stride == 0 || k + 1 == result.history.len()) {
+        println!("  iter {:>4}: objective {:.6e}, violation {:.4}", k, candidate.objective, candidate.violation);
+    }
+    
+    for (scenario, results) in scenarios.iter().zip(&result.best_results) {
+        println!("\nScenario '{}' with the best configuration:", scenario.name);
+        for metric in Metric::ALL {
+            println!("  {:<16} {:.6e}", metric.label(), metric.measure(results, goal.target_size));
+        }
+    }
+}
+
+fn print_comparison(cmp: &Comparison, json_output: bool, seconds: f64) {
+    let series = [("M_B", &cmp.m_b), ("M_L", &cmp.m_l), ("M_S", &cmp.m_s), ("M_N", &cmp.m_n), ("mempool", &cmp.mempool)];
+    let height = |h: Option<u32>| h.map_or("null".to_string(), |h| h.to_string());
+    if json_output {
+        println!("{{");
+        println!("  \"blocks\": {},", cmp.blocks);
+        println!("  \"max_mb\": [{}, {}],", cmp.a.max_mb, cmp.b.max_mb);
+        println!("  \"max_penalty\": [{}, {}],", cmp.a.max_penalty, cmp.b.max_penalty);
+        println!("  \"fees_total\": [{}, {}],", cmp.fees_total()[0], cmp.fees_total()[1]);
+        println!("  \"penalty_total\": [{}, {}],", cmp.penalty_total[0], cmp.penalty_total[1]);
+        println!("  \"divergence\": {{");
+        for (k, (name, diff)) in series.iter().enumerate() {
+            println!(
+                "    \"{}\": {{\"max_abs\": {}, \"max_at\": {}, \"rmse\": {}, \"mean_abs_pct\": {}}}{}",
+                name, diff.error.max_abs, diff.max_at, diff.error.rmse, diff.error.mean_abs_pct,
+                if k + 1 < series.len() { "," } else { "" }
+            );
+        }
+        println!("  }},");
+        let thresholds: Vec<String> = cmp
+            .thresholds
+            .iter()
+            .map(|t| format!("{{\"threshold\": {}, \"a\": {}, \"b\": {}}}", t.threshold, height(t.a), height(t.b)))
+            .collect();
+        println!("  \"time_to_threshold\": [{}],", thresholds.join(", "));
+        let join = |v: &[i64]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ");
+        println!("  \"M_B_a\": [{}],", join(&cmp.a.M_B[..cmp.blocks]));
+        println!("  \"M_B_b\": [{}]", join(&cmp.b.M_B[..cmp.blocks]));
+        println!("}}");
+        return;
+    }
+    
+    println!("A/B Comparison ({} blocks in {:.2}s)", cmp.blocks, seconds);
+    println!("==================================================\n");
+    println!("  {:<18} {:>16} {:>16}", "", "A", "B");
+    println!("  {:<18} {:>16} {:>16}", "Max Block Size", cmp.a.max_mb, cmp.b.max_mb);
+    println!("  {:<18} {:>16.6} {:>16.6}", "Max Penalty", cmp.a.max_penalty, cmp.b.max_penalty);
+    println!("  {:<18} {:>16} {:>16}", "Max Mempool", cmp.a.max_mempool, cmp.b.max_mempool);
+    println!("  {:<18} {:>16.6} {:>16.6}", "Fees Total", cmp.fees_total()[0], cmp.fees_total()[1]);
+    println!("  {:<18} {:>16.6} {:>16.6}", "Penalty Total", cmp.penalty_total[0], cmp.penalty_total[1]);
+    
+    println!("\nDivergence (B vs A):");
+    for (name, diff) in series {
+        println!(
+            "  {:<8} max {:>14} at block {:<8} rmse {:>14.1}  mean {:>7.2}%",
+            name, diff.error.max_abs, diff.max_at, diff.error.rmse, diff.error.mean_abs_pct
+        );
+    }
+    
+    println!("\nTime to threshold:");
+    for t in &cmp.thresholds {
+        let delta = t.delta().map_or(String::new(), |d| format!(" ({:+} blocks)", d));
+        println!("  M_B >= {:>12}: A {:>8}, B {:>8}{}", t.threshold, height(t.a), height(t.b), delta);
+    }
+}
+
+fn print_ensemble(outcomes: &[[f64; 6]], json_output: bool, seconds: f64) {
+    // Mean, standard deviation, min, median and max of each metric
+    let stats: Vec<[f64; 5]> = (0..Metric::ALL.len())
+        .map(|m| {
+            let mut values: Vec<f64> = outcomes.iter().map(|row| row[m]).collect();
+            values.sort_by(f64::total_cmp);
+            let count = values.len().max(1) as f64;
+            let mean = values.iter().sum::<f64>() / count;
+            let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count).sqrt();
+            let at = |k: usize| values.get(k).copied().unwrap_or(0.0);
+            [mean, std_dev, at(0), at(values.len() / 2), at(values.len().saturating_sub(1))]
+        })
+        .collect();
+    
+    if json_output {
+        println!("{{");
+        println!("  \"runs\": {},", outcomes.len());
+        println!("  \"metrics\": {{");
+        for (m, (metric, s)) in Metric::ALL.iter().zip(&stats).enumerate() {
+            println!(
+                "    \"{}\": {{\"mean\": {}, \"std_dev\": {}, \"min\": {}, \"median\": {}, \"max\": {}}}{}",
+                metric.label(), s[0], s[1], s[2], s[3], s[4],
+                if m + 1 < stats.len() { "," } else { "" }
+            );
+        }
+        println!("  }}");
+        println!("}}");
+        return;
+    }
+    
+    println!("Ensemble ({} runs in {:.2}s)", outcomes.len(), seconds);
+    println!("==================================================\n");
+    println!("  {:<16} {:>14} {:>14} {:>14} {:>14} {:>14}", "", "mean", "std dev", "min", "median", "max");
+    for (metric, s) in Metric::ALL.iter().zip(&stats) {
+        println!(
+            "  {:<16} {:>14.6e} {:>14.6e} {:>14.6e} {:>14.6e} {:>14.6e}",
+            metric.label(), s[0], s[1], s[2], s[3], s[4]
+        );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+//! Exact rolling median over a fixed-length window of block weights
+
+/// Rolling window keeping a sorted copy alongside insertion order, like the
+/// bisect-based lists in the original Python script.
+#[derive(Clone, Debug)]
+pub struct MedianWindow {
+    ring: Vec<i64>,
+    head: usize,
+    sorted: Vec<i64>,
+}
+
+impl MedianWindow {
+    /// Window of `len` entries all set to `fill`
+    pub fn new(len: usize, fill: i64) -> Self {
+        let len = len.max(1);
+        MedianWindow {
+            ring: vec![fill; len],
+            head: 0,
+            sorted: vec![fill; len],
+        }
+    }
+
+    /// Window seeded with `values`, oldest first. The window length is the
+    /// number of values.
+    pub fn from_values(values: &[i64]) -> Self {
+        if values.is_empty() {
+            return MedianWindow::new(1, 0);
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        MedianWindow {
+            ring: values.to_vec(),
+            head: 0,
+            sorted,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Window contents, oldest first
+    pub fn values(&self) -> Vec<i64> {
+        let mut values = Vec::with_capacity(self.ring.len());
+        values.extend_from_slice(&self.ring[self.head..]);
+        values.extend_from_slice(&self.ring[..self.head]);
+        values
+    }
+
+    /// Replace the oldest entry with `value`
+    pub fn push(&mut self, value: i64) {
+        let oldest = self.ring[self.head];
+        let pos = self.sorted.partition_point(|&x| x < oldest);
+        self.sorted.remove(pos);
+        let pos = self.sorted.partition_point(|&x| x < value);
+        self.sorted.insert(pos, value);
+
+        self.ring[self.head] = value;
+        self.head = (self.head + 1) % self.ring.len();
+    }
+
+    /// Median of the window; the mean of the two middle entries for even lengths
+    pub fn median(&self) -> i64 {
+        median_of_sorted(&self.sorted)
+    }
+}
+
+/// Median of an already sorted slice, valid for odd and even lengths
+pub fn median_of_sorted(sorted: &[i64]) -> i64 {
+    let len = sorted.len();
+    if len == 0 {
+        return 0;
+    }
+    (sorted[(len - 1) / 2] + sorted[len / 2]) / 2
+}
+
+/// Median of an unsorted slice, equal to `median_of_sorted` of the sorted
+/// slice; reorders the slice. Monotone input (a window that only grew or
+/// only shrank) is read directly.
+pub fn median_of_unsorted(values: &mut [i64]) -> i64 {
+    let len = values.len();
+    if len == 0 {
+        return 0;
+    }
+    // The two middle positions are the same ones in either sort order
+    if values.is_sorted() || values.is_sorted_by(|a, b| a >= b) {
+        return median_of_sorted(values);
+    }
+    let (lower, &mut upper, _) = values.select_nth_unstable(len / 2);
+    let lower_middle = if len.is_multiple_of(2) { lower.iter().copied().max().unwrap_or(upper) } else { upper };
+    (lower_middle + upper) / 2
+}
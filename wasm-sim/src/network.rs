@@ -0,0 +1,193 @@
+//! Multi-miner network: hashrate shares, block propagation delay and orphan risk
+//!
+//! Blocks are found by a Poisson process. After a block is found, the run
+//! draws the exponential interval until the rest of the network finds a
+//! competing block. If that comes before the block has propagated, the rival
+//! block takes the height and the orphaned block's txs return to the mempool.
+//! On average this happens with probability
+//! `1 - exp(-(1 - share) * delay / block_time)`, which miners use to price
+//! orphan risk when building blocks. Propagation delay grows with block size
+//! through relay bandwidth and validation time, which makes orphan risk a
+//! cost of large blocks alongside the penalty. Miners don't compete with
+//! themselves, so large miners carry less of that cost.
+
+use crate::lib_core::SimulationConfig;
+
+/// One miner (or pool) on the network
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Miner {
+    /// Fraction of total hashrate
+    pub hashrate_share: f64,
+    /// Relay bandwidth to the rest of the network in bytes per second
+    pub bandwidth: f64,
+    /// Fixed relay latency in seconds
+    pub latency: f64,
+}
+
+/// Miners plus network-wide propagation parameters
+#[derive(Clone, Debug)]
+pub struct NetworkModel {
+    pub miners: Vec<Miner>,
+    /// Bytes per second a receiving node validates
+    pub validation_rate: f64,
+    /// Whether block building accounts for the marginal orphan cost of each tx
+    pub orphan_aware: bool,
+}
+
+impl NetworkModel {
+    /// Network described by the scalar config fields: one miner with
+    /// `largest_miner_share` of the hashrate and the rest split evenly
+    pub fn from_config(config: &SimulationConfig) -> Option<NetworkModel> {
+        if config.miners == 0 {
+            return None;
+        }
+        let count = config.miners as usize;
+        let largest = if count == 1 { 1.0 } else { config.largest_miner_share.clamp(0.0, 1.0) };
+        let rest = if count > 1 { (1.0 - largest) / (count - 1) as f64 } else { 0.0 };
+
+        let miners = (0..count)
+            .map(|k| Miner {
+                hashrate_share: if k == 0 { largest } else { rest },
+                bandwidth: config.miner_bandwidth,
+                latency: config.propagation_latency,
+            })
+            .collect();
+        Some(NetworkModel {
+            miners,
+            validation_rate: config.validation_rate,
+            orphan_aware: true,
+        })
+    }
+
+    /// Network with the given hashrate shares (normalised to sum to 1) and
+    /// identical connectivity
+    pub fn from_shares(shares: &[f64], bandwidth: f64, latency: f64, validation_rate: f64) -> Result<NetworkModel, String> {
+        let total: f64 = shares.iter().sum();
+        if !shares.iter().all(|&share| share >= 0.0) || total <= 0.0 {
+            return Err("hashrate shares must be non-negative with a positive sum".to_string());
+        }
+        Ok(NetworkModel {
+            miners: shares
+                .iter()
+                .map(|&share| Miner { hashrate_share: share / total, bandwidth, latency })
+                .collect(),
+            validation_rate,
+            orphan_aware: true,
+        })
+    }
+
+    /// Pick the miner of the next block given a uniform sample in [0, 1).
+    /// The model must have at least one miner.
+    pub fn pick_miner(&self, sample: f64) -> usize {
+        let mut acc = 0.0;
+        for (k, miner) in self.miners.iter().enumerate() {
+            acc += miner.hashrate_share;
+            if sample < acc {
+                return k;
+            }
+        }
+        self.miners.len() - 1
+    }
+
+    /// Pick the miner of a block competing with one from `miner`, given a
+    /// uniform sample in [0, 1): one of the others, by hashrate share
+    pub fn pick_rival(&self, miner: usize, sample: f64) -> usize {
+        let target = sample * (1.0 - self.miners[miner].hashrate_share);
+        let mut acc = 0.0;
+        let mut rival = miner;
+        for (k, other) in self.miners.iter().enumerate().filter(|&(k, _)| k != miner) {
+            acc += other.hashrate_share;
+            rival = k;
+            if target < acc {
+                break;
+            }
+        }
+        rival
+    }
+
+    /// Seconds for a block of `size` bytes from `miner` to reach and be
+    /// validated by the rest of the network
+    pub fn propagation_delay(&self, miner: usize, size: i64) -> f64 {
+        let m = &self.miners[miner];
+        m.latency + size as f64 / m.bandwidth.max(1.0) + size as f64 / self.validation_rate.max(1.0)
+    }
+
+    /// Expected probability a block of `size` bytes from `miner` is orphaned
+    pub fn orphan_probability(&self, miner: usize, size: i64, block_time: f64) -> f64 {
+        let competing = 1.0 - self.miners[miner].hashrate_share;
+        1.0 - (-competing * self.propagation_delay(miner, size) / block_time).exp()
+    }
+
+    /// Expected reward lost by growing a block from `size` by `tx_size` bytes
+    pub fn marginal_orphan_cost(&self, miner: usize, size: i64, tx_size: i64, reward: f64, block_time: f64) -> f64 {
+        let before = self.orphan_probability(miner, size, block_time);
+        let after = self.orphan_probability(miner, size + tx_size, block_time);
+        reward * (after - before)
+    }
+}
+
+/// Outcome of the blocks one miner produced
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinerStats {
+    pub hashrate_share: f64,
+    pub blocks_mined: u32,
+    pub blocks_orphaned: u32,
+    /// Total bytes of the miner's blocks
+    pub bytes_mined: i64,
+    /// Base reward after penalty plus fees, for blocks that were not orphaned
+    pub revenue: f64,
+}
+
+impl MinerStats {
+    pub fn orphan_rate(&self) -> f64 {
+        if self.blocks_mined == 0 { 0.0 } else { self.blocks_orphaned as f64 / self.blocks_mined as f64 }
+    }
+
+    /// Share of total network revenue relative to share of hashrate
+    pub fn revenue_ratio(&self, total_revenue: f64) -> f64 {
+        if total_revenue <= 0.0 || self.hashrate_share <= 0.0 {
+            0.0
+        } else {
+            self.revenue / total_revenue / self.hashrate_share
+        }
+    }
+}
+
+/// Network-wide results of a run
+#[derive(Clone, Debug, Default)]
+pub struct NetworkStats {
+    pub miners: Vec<MinerStats>,
+    /// Miner of the block at every height
+    pub block_miner: Vec<u32>,
+    /// Expected orphan probability of the block at every height
+    pub orphan_probability: Vec<f64>,
+    /// Heights at which a block was orphaned and re-mined by a rival
+    pub orphaned_blocks: Vec<u32>,
+}
+
+impl NetworkStats {
+    pub fn new(model: &NetworkModel, capacity: usize) -> Self {
+        NetworkStats {
+            miners: model
+                .miners
+                .iter()
+                .map(|m| MinerStats { hashrate_share: m.hashrate_share, ..MinerStats::default() })
+                .collect(),
+            block_miner: Vec::with_capacity(capacity),
+            orphan_probability: Vec::with_capacity(capacity),
+            orphaned_blocks: Vec::new(),
+        }
+    }
+
+    pub fn orphan_rate(&self) -> f64 {
+        if self.block_miner.is_empty() {
+            0.0
+        } else {
+            self.orphaned_blocks.len() as f64 / self.block_miner.len() as f64
+        }
+    }
+
+    pub fn total_revenue(&self) -> f64 {
+        self.miners.iter().map(|m| m.revenue).sum()
+    }
+}
@@ -0,0 +1,304 @@
+//! Goal-seeking search over rule parameters
+//!
+//! A goal is an objective on one run metric plus constraints on others, each
+//! measured in a named scenario (e.g. sustained demand, a spam attack). The
+//! search is Nelder-Mead over the factor ranges scaled to the unit cube.
+//! Candidates are ranked by total constraint violation first and the
+//! objective second, so the simplex moves towards the feasible region before
+//! it optimises. Nelder-Mead only compares candidates, which makes that
+//! ordering enough.
+
+use crate::batch::parallel_map;
+use crate::lib_core::{run_simulation_with_inputs, SimulationConfig, SimulationInputs, SimulationResults};
+use crate::lockstep::{run_totals, RunTotals};
+use crate::sensitivity::{Factor, Metric};
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// A setting the rules are evaluated in
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub config: SimulationConfig,
+    pub inputs: SimulationInputs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    AtMost(f64),
+    AtLeast(f64),
+}
+
+/// A metric that must stay within a bound in one scenario
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Constraint {
+    /// Index into the scenarios
+    pub scenario: usize,
+    pub metric: Metric,
+    pub bound: Bound,
+}
+
+impl Constraint {
+    /// Parse `[scenario:]metric<=value` or `[scenario:]metric>=value`
+    pub fn parse(spec: &str, scenarios: &[Scenario]) -> Result<Constraint, String> {
+        let (lhs, rhs, at_most) = if let Some((lhs, rhs)) = spec.split_once("<=") {
+            (lhs, rhs, true)
+        } else if let Some((lhs, rhs)) = spec.split_once(">=") {
+            (lhs, rhs, false)
+        } else {
+            return Err(format!("'{}': expected metric<=value or metric>=value", spec));
+        };
+        let (scenario, metric) = parse_target(lhs, scenarios)?;
+        let value: f64 = rhs.trim().parse().map_err(|_| format!("'{}': invalid value", spec))?;
+        let bound = if at_most { Bound::AtMost(value) } else { Bound::AtLeast(value) };
+        Ok(Constraint { scenario, metric, bound })
+    }
+
+    /// Relative amount by which `value` breaks the bound (0 when satisfied)
+    pub fn violation(&self, value: f64) -> f64 {
+        let (excess, limit) = match self.bound {
+            Bound::AtMost(limit) => (value - limit, limit),
+            Bound::AtLeast(limit) => (limit - value, limit),
+        };
+        excess.max(0.0) / limit.abs().max(f64::MIN_POSITIVE)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sense {
+    Minimize,
+    Maximize,
+}
+
+/// The metric to optimise in one scenario
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Objective {
+    pub scenario: usize,
+    pub metric: Metric,
+    pub sense: Sense,
+}
+
+impl Objective {
+    /// Parse `min:[scenario:]metric` or `max:[scenario:]metric`
+    pub fn parse(spec: &str, scenarios: &[Scenario]) -> Result<Objective, String> {
+        let (sense, target) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("'{}': expected min:metric or max:metric", spec))?;
+        let sense = match sense {
+            "min" => Sense::Minimize,
+            "max" => Sense::Maximize,
+            _ => return Err(format!("'{}': expected min or max", sense)),
+        };
+        let (scenario, metric) = parse_target(target, scenarios)?;
+        Ok(Objective { scenario, metric, sense })
+    }
+}
+
+/// Parse `[scenario:]metric`; without a scenario name the first scenario is used
+fn parse_target(spec: &str, scenarios: &[Scenario]) -> Result<(usize, Metric), String> {
+    let (scenario, metric) = match spec.trim().split_once(':') {
+        Some((name, metric)) => {
+            let k = scenarios
+                .iter()
+                .position(|s| s.name == name)
+                .ok_or_else(|| format!("unknown scenario '{}'", name))?;
+            (k, metric)
+        }
+        None => (0, spec.trim()),
+    };
+    let metric = Metric::parse(metric).ok_or_else(|| format!("unknown metric '{}'", metric))?;
+    Ok((scenario, metric))
+}
+
+#[derive(Clone, Debug)]
+pub struct Goal {
+    pub objective: Objective,
+    pub constraints: Vec<Constraint>,
+    /// Size for the `time_to_target` metric
+    pub target_size: i64,
+}
+
+/// One evaluated set of parameter values
+#[derive(Clone, Debug, Default)]
+pub struct Candidate {
+    /// Parameter values, in factor order
+    pub values: Vec<f64>,
+    pub objective: f64,
+    /// Sum of relative constraint violations
+    pub violation: f64,
+}
+
+impl Candidate {
+    pub fn feasible(&self) -> bool {
+        self.violation <= 0.0
+    }
+
+    fn rank(&self, other: &Candidate, sense: Sense) -> Ordering {
+        let by_objective = match sense {
+            Sense::Minimize => self.objective.total_cmp(&other.objective),
+            Sense::Maximize => other.objective.total_cmp(&self.objective),
+        };
+        self.violation.total_cmp(&other.violation).then(by_objective)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OptimizerResult {
+    pub factors: Vec<Factor>,
+    pub best: Candidate,
+    /// Best candidate after each iteration
+    pub history: Vec<Candidate>,
+    pub evaluations: usize,
+    /// Runs of the best configuration, one per scenario
+    pub best_results: Vec<SimulationResults>,
+}
+
+/// Apply parameter values to a scenario's config
+fn configure(scenario: &Scenario, factors: &[Factor], values: &[f64]) -> SimulationConfig {
+    let mut config = scenario.config;
+    for (factor, &value) in factors.iter().zip(values) {
+        factor.param.apply(&mut config, value);
+    }
+    config
+}
+
+/// Search `factors` for the best configuration under `goal`, with at most
+/// `max_evaluations` candidates (each runs every scenario) on up to `threads`
+/// threads (0 = one per core)
+pub fn optimize(
+    scenarios: &[Scenario],
+    factors: &[Factor],
+    goal: &Goal,
+    max_evaluations: usize,
+    threads: usize,
+) -> Result<OptimizerResult, String> {
+    if scenarios.is_empty() {
+        return Err("the optimizer needs at least one scenario".to_string());
+    }
+    let mut targets = std::iter::once(goal.objective.scenario).chain(goal.constraints.iter().map(|c| c.scenario));
+    if let Some(k) = targets.find(|&k| k >= scenarios.len()) {
+        return Err(format!("goal refers to scenario {} but there are only {}", k, scenarios.len()));
+    }
+
+    let k = factors.len();
+    let sense = goal.objective.sense;
+    let evaluations = Cell::new(0);
+
+    // Candidates evaluated together run as one batch per scenario, on the
+    // lockstep engine where it covers them
+    let evaluate_all = |points: &[Vec<f64>]| -> Vec<Candidate> {
+        evaluations.set(evaluations.get() + points.len());
+        let values: Vec<Vec<f64>> =
+            points.iter().map(|u| factors.iter().zip(u).map(|(f, &x)| f.value(x)).collect()).collect();
+        let totals: Vec<Vec<RunTotals>> = scenarios
+            .iter()
+            .map(|scenario| {
+                let configs: Vec<SimulationConfig> = values.iter().map(|v| configure(scenario, factors, v)).collect();
+                run_totals(&configs, &scenario.inputs, threads, goal.target_size)
+            })
+            .collect();
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(c, values)| {
+                let measure = |scenario: usize, metric: Metric| metric.of_totals(&totals[scenario][c]);
+                Candidate {
+                    objective: measure(goal.objective.scenario, goal.objective.metric),
+                    violation: goal
+                        .constraints
+                        .iter()
+                        .map(|c| c.violation(measure(c.scenario, c.metric)))
+                        .sum(),
+                    values,
+                }
+            })
+            .collect()
+    };
+    let evaluate = |u: &[f64]| -> Candidate { evaluate_all(&[u.to_vec()]).remove(0) };
+    let clamp = |x: Vec<f64>| -> Vec<f64> { x.into_iter().map(|v| v.clamp(0.0, 1.0)).collect() };
+
+    // Start around the first scenario's current values
+    let start: Vec<f64> = factors
+        .iter()
+        .map(|f| {
+            let span = f.high - f.low;
+            if span != 0.0 { (f.param.get(&scenarios[0].config) - f.low) / span } else { 0.0 }
+        })
+        .collect();
+    let start = clamp(start);
+    let mut vertices = vec![start.clone()];
+    for i in 0..k {
+        let mut vertex = start.clone();
+        vertex[i] += if vertex[i] <= 0.75 { 0.25 } else { -0.25 };
+        vertices.push(vertex);
+    }
+    let candidates = evaluate_all(&vertices);
+    let mut simplex: Vec<(Vec<f64>, Candidate)> = vertices.into_iter().zip(candidates).collect();
+
+    let mut history = Vec::new();
+    while evaluations.get() < max_evaluations && k > 0 {
+        simplex.sort_by(|a, b| a.1.rank(&b.1, sense));
+        history.push(simplex[0].1.clone());
+
+        let spread = simplex[1..]
+            .iter()
+            .flat_map(|(x, _)| x.iter().zip(&simplex[0].0).map(|(a, b)| (a - b).abs()))
+            .fold(0.0, f64::max);
+        if spread < 1e-4 {
+            break;
+        }
+
+        let centroid: Vec<f64> = (0..k).map(|d| simplex[..k].iter().map(|(x, _)| x[d]).sum::<f64>() / k as f64).collect();
+        let worst = simplex[k].0.clone();
+        let towards = |t: f64| -> Vec<f64> { clamp((0..k).map(|d| centroid[d] + t * (worst[d] - centroid[d])).collect()) };
+
+        let reflected = towards(-1.0);
+        let f_reflected = evaluate(&reflected);
+
+        if f_reflected.rank(&simplex[0].1, sense) == Ordering::Less {
+            let expanded = towards(-2.0);
+            let f_expanded = evaluate(&expanded);
+            simplex[k] = if f_expanded.rank(&f_reflected, sense) == Ordering::Less {
+                (expanded, f_expanded)
+            } else {
+                (reflected, f_reflected)
+            };
+        } else if f_reflected.rank(&simplex[k - 1].1, sense) == Ordering::Less {
+            simplex[k] = (reflected, f_reflected);
+        } else {
+            let outside = f_reflected.rank(&simplex[k].1, sense) == Ordering::Less;
+            let contracted = towards(if outside { -0.5 } else { 0.5 });
+            let f_contracted = evaluate(&contracted);
+            let beats = if outside { &f_reflected } else { &simplex[k].1 };
+            if f_contracted.rank(beats, sense) != Ordering::Greater {
+                simplex[k] = (contracted, f_contracted);
+            } else {
+                // Shrink towards the best vertex
+                let best = simplex[0].0.clone();
+                let shrunk: Vec<Vec<f64>> = simplex[1..]
+                    .iter()
+                    .map(|(x, _)| (0..k).map(|d| best[d] + 0.5 * (x[d] - best[d])).collect())
+                    .collect();
+                let candidates = evaluate_all(&shrunk);
+                for (vertex, shrunk) in simplex.iter_mut().skip(1).zip(shrunk.into_iter().zip(candidates)) {
+                    *vertex = shrunk;
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.rank(&b.1, sense));
+    let best = simplex[0].1.clone();
+    history.push(best.clone());
+
+    let best_results = parallel_map(scenarios, threads, |_, scenario| {
+        run_simulation_with_inputs(configure(scenario, factors, &best.values), &scenario.inputs)
+    });
+
+    Ok(OptimizerResult {
+        factors: factors.to_vec(),
+        best,
+        history,
+        evaluations: evaluations.get(),
+        best_results,
+    })
+}
@@ -0,0 +1,149 @@
+//! Node resource model: turns the M_B series into chain growth, bandwidth and
+//! verification load, and finds where a reference home node falls behind
+
+use crate::lib_core::{SimulationConfig, SimulationResults};
+
+/// Per-byte and per-input costs of handling chain data
+#[derive(Clone, Copy, Debug)]
+pub struct CostProfile {
+    /// Average bytes per tx input, used to turn block weight into inputs
+    pub bytes_per_input: f64,
+    /// Verification CPU seconds per input (ring signature check)
+    pub seconds_per_input: f64,
+    /// Verification CPU seconds per byte (range proofs, hashing, parsing)
+    pub seconds_per_byte: f64,
+    /// Bytes transferred per byte of chain data: tx relay plus block relay
+    /// plus uploads to peers
+    pub relay_factor: f64,
+}
+
+impl Default for CostProfile {
+    fn default() -> Self {
+        CostProfile {
+            bytes_per_input: 1_000.0,
+            seconds_per_input: 0.0015,
+            seconds_per_byte: 0.000_000_25,
+            relay_factor: 4.0,
+        }
+    }
+}
+
+/// Specs of the reference node whose viability is checked
+#[derive(Clone, Copy, Debug)]
+pub struct NodeProfile {
+    /// Sustained bandwidth in bytes per second
+    pub bandwidth: f64,
+    /// Disk available for the chain in bytes
+    pub storage: f64,
+    /// CPU cores available for verification
+    pub cores: f64,
+    /// Chain size in bytes before the first simulated block
+    pub initial_chain_size: f64,
+}
+
+impl Default for NodeProfile {
+    fn default() -> Self {
+        NodeProfile {
+            bandwidth: 2_500_000.0,  // 20 Mbit/s
+            storage: 1_000_000_000_000.0,  // 1 TB
+            cores: 4.0,
+            initial_chain_size: 0.0,
+        }
+    }
+}
+
+/// Which resource the reference node ran out of first
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bottleneck {
+    Bandwidth,
+    Verification,
+    Storage,
+}
+
+/// Resource usage over a run
+#[derive(Clone, Debug, Default)]
+pub struct ResourceReport {
+    /// Cumulative chain size in bytes after each block
+    pub chain_size: Vec<f64>,
+    /// Bytes per day the node transfers, over the blocks found in the
+    /// trailing 24 hours of the run's timestamps
+    pub bandwidth_per_day: Vec<f64>,
+    /// Verification CPU seconds for each block
+    pub verify_seconds: Vec<f64>,
+    /// First block at which the reference node falls behind, and why
+    pub falls_behind_at: Option<(u32, Bottleneck)>,
+}
+
+impl ResourceReport {
+    /// Derive resource usage from a run's block sizes
+    pub fn from_results(
+        results: &SimulationResults,
+        config: &SimulationConfig,
+        costs: &CostProfile,
+        node: &NodeProfile,
+    ) -> ResourceReport {
+        let block_time = config.block_time.max(1) as f64;
+        // When each block was found; nominal intervals if the run has no timestamps
+        let found_at = |i: usize| results.timestamps.get(i).copied().unwrap_or((i + 1) as f64 * block_time);
+        let daily_capacity = node.bandwidth * 86_400.0;
+        let cpu_per_block = node.cores * block_time;
+
+        let mut report = ResourceReport {
+            chain_size: Vec::with_capacity(results.M_B.len()),
+            bandwidth_per_day: Vec::with_capacity(results.M_B.len()),
+            verify_seconds: Vec::with_capacity(results.M_B.len()),
+            falls_behind_at: None,
+        };
+
+        let mut chain_size = node.initial_chain_size;
+        let mut trailing_day: f64 = 0.0;
+        let mut day_start = 0;
+        for (i, &m_b) in results.M_B.iter().enumerate() {
+            let bytes = m_b as f64;
+            chain_size += bytes;
+
+            let now = found_at(i);
+            trailing_day += bytes;
+            while day_start < i && found_at(day_start) <= now - 86_400.0 {
+                trailing_day -= results.M_B[day_start] as f64;
+                day_start += 1;
+            }
+            // Scale a partial first day up to a full day
+            let covered = now.clamp(f64::MIN_POSITIVE, 86_400.0);
+            let per_day = trailing_day * costs.relay_factor * 86_400.0 / covered;
+
+            let inputs = bytes / costs.bytes_per_input.max(1.0);
+            let verify = inputs * costs.seconds_per_input + bytes * costs.seconds_per_byte;
+
+            report.chain_size.push(chain_size);
+            report.bandwidth_per_day.push(per_day);
+            report.verify_seconds.push(verify);
+
+            if report.falls_behind_at.is_none() {
+                let bottleneck = if verify > cpu_per_block {
+                    Some(Bottleneck::Verification)
+                } else if now >= 86_400.0 && per_day > daily_capacity {
+                    Some(Bottleneck::Bandwidth)
+                } else if chain_size > node.storage {
+                    Some(Bottleneck::Storage)
+                } else {
+                    None
+                };
+                report.falls_behind_at = bottleneck.map(|b| (results.indices.get(i).copied().unwrap_or(i as u32), b));
+            }
+        }
+        report
+    }
+
+    pub fn final_chain_size(&self) -> f64 {
+        self.chain_size.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn peak_bandwidth_per_day(&self) -> f64 {
+        self.bandwidth_per_day.iter().copied().fold(0.0, f64::max)
+    }
+
+    pub fn peak_verify_seconds(&self) -> f64 {
+        self.verify_seconds.iter().copied().fold(0.0, f64::max)
+    }
+}
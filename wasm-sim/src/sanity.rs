@@ -0,0 +1,70 @@
+//! Sanity cap (A_C) schedules
+//!
+//! The proposal's cap is `A_C = A_S * (1 + 5/(4*10^6))^(K_B - K_S)`: compound
+//! growth of ~40% a year from `sanity_start_weight` at `sanity_start_block`.
+//! Alternative shapes use the same start weight and growth rate.
+
+use crate::lib_core::SimulationConfig;
+
+/// Growth per 2-minute block of the proposal's cap (~40% a year)
+pub const DEFAULT_SANITY_GROWTH_RATE: f64 = 5.0 / (4.0 * 1_000_000.0);
+
+/// Shape of the sanity cap over time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SanitySchedule {
+    /// `A_S * (1 + rate)^blocks`, the proposal's formula
+    Compound,
+    /// `A_S * (1 + rate * blocks)`
+    Linear,
+    /// Compound growth applied once per year
+    Stepwise,
+    /// `multiple * M_L`, following the long term median instead of time
+    TiedToMl { multiple: f64 },
+}
+
+impl SanitySchedule {
+    pub fn parse(name: &str, multiple: f64) -> Option<SanitySchedule> {
+        match name {
+            "compound" => Some(SanitySchedule::Compound),
+            "linear" => Some(SanitySchedule::Linear),
+            "stepwise" => Some(SanitySchedule::Stepwise),
+            "ml" => Some(SanitySchedule::TiedToMl { multiple }),
+            _ => None,
+        }
+    }
+
+    /// Numeric id used by the CLI and the WASM wrapper (0 = compound,
+    /// 1 = linear, 2 = stepwise, 3 = tied to M_L with `multiple`)
+    pub fn from_id(id: u32, multiple: f64) -> SanitySchedule {
+        match id {
+            1 => SanitySchedule::Linear,
+            2 => SanitySchedule::Stepwise,
+            3 => SanitySchedule::TiedToMl { multiple },
+            _ => SanitySchedule::Compound,
+        }
+    }
+
+    /// A_C for the block at `height`; `i64::MAX` (no cap) before `sanity_start_block`
+    pub fn cap(self, config: &SimulationConfig, height: usize, m_l: i64) -> i64 {
+        if height < config.sanity_start_block as usize {
+            return i64::MAX;
+        }
+        // The rate is per 2-minute block, so elapsed blocks are scaled to keep
+        // the same growth per year at other block times.
+        let blocks_elapsed = config.reference_blocks((height - config.sanity_start_block as usize) as f64);
+        let start = config.sanity_start_weight as f64;
+        let rate = config.sanity_growth_rate;
+
+        let cap = match self {
+            SanitySchedule::Compound => start * (1.0 + rate).powf(blocks_elapsed),
+            SanitySchedule::Linear => start * (1.0 + rate * blocks_elapsed),
+            SanitySchedule::Stepwise => {
+                let year = config.reference_blocks(config.blocks_per_year());
+                let whole_years = (blocks_elapsed / year).floor();
+                start * (1.0 + rate).powf(whole_years * year)
+            }
+            SanitySchedule::TiedToMl { multiple } => multiple * m_l as f64,
+        };
+        cap as i64
+    }
+}
@@ -0,0 +1,146 @@
+//! Initial median window contents: starting a run from real or custom history,
+//! or where a previous run left off, instead of windows filled with
+//! `steady_state`
+
+use crate::history::ChainHistory;
+use crate::lib_core::SimulationConfig;
+use crate::median::MedianWindow;
+
+/// Contents of the long and short median windows at the start of a run
+#[derive(Clone, Debug, Default)]
+pub struct WindowSeed {
+    /// Long-term weights, oldest first
+    pub long_weights: Vec<i64>,
+    /// Short-term weights, oldest first
+    pub short_weights: Vec<i64>,
+    /// Weight of the block mined just before the run starts
+    pub last_block_weight: i64,
+}
+
+impl WindowSeed {
+    /// Windows left behind by a sequence of block weights (oldest first) under
+    /// the config's median rules. Blocks older than the sequence are taken to
+    /// be at `steady_state`.
+    pub fn from_block_weights(weights: &[i64], config: &SimulationConfig) -> Self {
+        let (long_window, short_window) = replay_weights(weights.iter().copied(), config);
+        WindowSeed {
+            long_weights: long_window.values(),
+            short_weights: short_window.values(),
+            last_block_weight: weights.last().copied().unwrap_or(config.steady_state),
+        }
+    }
+
+    /// Windows as they stand at the end of a recorded chain history
+    pub fn from_history(history: &ChainHistory, config: &SimulationConfig) -> Self {
+        WindowSeed::from_block_weights(&history.weights(), config)
+    }
+
+    /// Windows after blocks held at `from` bytes and then ramped linearly to
+    /// `to` over the last `ramp_blocks` blocks. A fast ramp leaves M_S near
+    /// `to` while M_L still lags behind.
+    pub fn ramp(from: i64, to: i64, ramp_blocks: usize, config: &SimulationConfig) -> Self {
+        let len_l = config.long_window_len();
+        let ramp_blocks = ramp_blocks.clamp(1, len_l);
+        let weights: Vec<i64> = (0..len_l)
+            .map(|k| {
+                let into_ramp = (k + ramp_blocks + 1).saturating_sub(len_l);
+                from + ((to - from) as f64 * into_ramp as f64 / ramp_blocks as f64) as i64
+            })
+            .collect();
+        WindowSeed::from_block_weights(&weights, config)
+    }
+
+    /// Window dump text: `last_block_weight`, `long` and `short` lines, each
+    /// the key followed by its values (oldest first), separated by commas,
+    /// tabs or spaces. `#` comments and blank lines are skipped.
+    pub fn to_text(&self) -> String {
+        let join = |values: &[i64]| values.iter().map(i64::to_string).collect::<Vec<_>>().join(",");
+        format!(
+            "# median windows, oldest first\nlast_block_weight,{}\nlong,{}\nshort,{}\n",
+            self.last_block_weight,
+            join(&self.long_weights),
+            join(&self.short_weights)
+        )
+    }
+
+    /// Parse a window dump written by `to_text`
+    pub fn from_text(text: &str) -> Result<WindowSeed, String> {
+        let mut last_block_weight = None;
+        let mut long_weights = None;
+        let mut short_weights = None;
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split([',', '\t', ' ']).filter(|f| !f.is_empty());
+            let key = fields.next().unwrap_or_default();
+            let values = fields
+                .map(|field| field.parse::<i64>().map_err(|_| format!("line {}: invalid weight '{}'", line_no + 1, field)))
+                .collect::<Result<Vec<i64>, String>>()?;
+            match key {
+                "last_block_weight" => match values[..] {
+                    [weight] => last_block_weight = Some(weight),
+                    _ => return Err(format!("line {}: expected one last block weight", line_no + 1)),
+                },
+                "long" => long_weights = Some(values),
+                "short" => short_weights = Some(values),
+                _ => return Err(format!("line {}: unknown key '{}'", line_no + 1, key)),
+            }
+        }
+
+        match (long_weights, short_weights, last_block_weight) {
+            (Some(long_weights), Some(short_weights), Some(last_block_weight))
+                if !long_weights.is_empty() && !short_weights.is_empty() =>
+            {
+                Ok(WindowSeed { long_weights, short_weights, last_block_weight })
+            }
+            _ => Err("window dump needs last_block_weight and non-empty long and short lines".to_string()),
+        }
+    }
+
+    /// Read and parse a window dump file
+    pub fn load(path: &str) -> Result<WindowSeed, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        WindowSeed::from_text(&text)
+    }
+
+    /// Write the windows as a dump file, e.g. a run's `final_windows` to
+    /// seed the next run of a chain
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Fit seed values into a window of `len` entries: the newest `len` values
+    /// are kept and missing older entries are filled with `fill`
+    pub fn fit(values: &[i64], len: usize, fill: i64) -> Vec<i64> {
+        if values.len() >= len {
+            values[values.len() - len..].to_vec()
+        } else {
+            let mut fitted = vec![fill; len - values.len()];
+            fitted.extend_from_slice(values);
+            fitted
+        }
+    }
+}
+
+/// Run the simulation's long/short median rules over a sequence of block
+/// weights, starting from windows filled with `steady_state`, and return the
+/// final windows
+fn replay_weights(weights: impl Iterator<Item = i64>, config: &SimulationConfig) -> (MedianWindow, MedianWindow) {
+    let mut long_window = MedianWindow::new(config.long_window_len(), config.steady_state);
+    let mut short_window = MedianWindow::new(config.short_window_len(), config.steady_state);
+    let mut m_l_prev = config.steady_state;
+
+    for weight in weights {
+        let m_l = long_window.median();
+
+        let ml_upper = (config.ml_mult * m_l_prev as f64) as i64;
+        let ml_lower = (m_l_prev as f64 / config.ml_mult) as i64;
+        long_window.push(weight.min(ml_upper).max(config.z_m).max(ml_lower));
+        short_window.push(weight.max(m_l));
+        m_l_prev = m_l;
+    }
+    (long_window, short_window)
+}
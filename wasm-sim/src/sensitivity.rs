@@ -0,0 +1,203 @@
+//! Global sensitivity analysis: which config parameters drive the outcome
+//!
+//! Variance-based (Sobol) indices estimated by Monte Carlo: two sample
+//! matrices A and B drawn uniformly at random over the factor ranges, plus one
+//! matrix per factor that takes that factor's column from B and the rest from
+//! A. That is `samples * (factors + 2)` runs, done as one batch. The samples
+//! are pseudo-random, not a low-discrepancy Sobol sequence, so the indices
+//! converge as `1/sqrt(samples)`. First-order indices use Saltelli's estimator
+//! and total-order indices Jansen's.
+
+use crate::events::Param;
+use crate::lib_core::{Rng, SimulationConfig, SimulationInputs, SimulationResults};
+use crate::lockstep::{run_totals, RunTotals};
+
+/// A parameter varied uniformly over `[low, high]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Factor {
+    pub param: Param,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Factor {
+    /// Factor spanning `spread` either side of the parameter's value in `config`
+    pub fn around(param: Param, config: &SimulationConfig, spread: f64) -> Factor {
+        let value = param.get(config);
+        Factor { param, low: value * (1.0 - spread), high: value * (1.0 + spread) }
+    }
+
+    /// Parse `name:low:high`
+    pub fn parse(spec: &str) -> Result<Factor, String> {
+        let fields: Vec<&str> = spec.split(':').collect();
+        if fields.len() != 3 {
+            return Err(format!("'{}': expected name:low:high", spec));
+        }
+        let param = Param::parse(fields[0]).ok_or_else(|| format!("unknown parameter '{}'", fields[0]))?;
+        let bound = |s: &str| s.parse::<f64>().map_err(|_| format!("'{}': invalid bound '{}'", spec, s));
+        Ok(Factor { param, low: bound(fields[1])?, high: bound(fields[2])? })
+    }
+
+    /// Parameter value at position `u` in [0, 1] of the range
+    pub fn value(&self, u: f64) -> f64 {
+        self.low + u * (self.high - self.low)
+    }
+}
+
+/// Scalar outcomes of a run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    MaxMb,
+    MaxPenalty,
+    MaxMempool,
+    CumulativeFees,
+    FinalMb,
+    /// Blocks until M_B first reaches the target size (the run length if never)
+    TimeToTarget,
+}
+
+/// Metrics the sensitivity indices are computed for
+pub const SENSITIVITY_METRICS: [Metric; 3] = [Metric::MaxMb, Metric::MaxPenalty, Metric::TimeToTarget];
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::MaxMb,
+        Metric::MaxPenalty,
+        Metric::MaxMempool,
+        Metric::CumulativeFees,
+        Metric::FinalMb,
+        Metric::TimeToTarget,
+    ];
+
+    pub fn parse(name: &str) -> Option<Metric> {
+        Metric::ALL.into_iter().find(|metric| metric.label() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::MaxMb => "max_mb",
+            Metric::MaxPenalty => "max_penalty",
+            Metric::MaxMempool => "max_mempool",
+            Metric::CumulativeFees => "cumulative_fees",
+            Metric::FinalMb => "final_mb",
+            Metric::TimeToTarget => "time_to_target",
+        }
+    }
+
+    pub fn measure(self, results: &SimulationResults, target_size: i64) -> f64 {
+        self.of_totals(&RunTotals::from_results(results, target_size))
+    }
+
+    pub fn of_totals(self, totals: &RunTotals) -> f64 {
+        match self {
+            Metric::MaxMb => totals.max_mb as f64,
+            Metric::MaxPenalty => totals.max_penalty,
+            Metric::MaxMempool => totals.max_mempool as f64,
+            Metric::CumulativeFees => totals.cumulative_fees,
+            Metric::FinalMb => totals.final_mb as f64,
+            Metric::TimeToTarget => totals.target_reached_at.unwrap_or(totals.blocks) as f64,
+        }
+    }
+}
+
+/// Sobol indices of one factor for one metric
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SobolIndex {
+    /// Share of output variance due to the factor alone
+    pub first_order: f64,
+    /// Share of output variance the factor is involved in, interactions included
+    pub total_order: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SensitivityReport {
+    pub factors: Vec<Factor>,
+    /// Indexed `[factor][metric]`, metrics in `SENSITIVITY_METRICS` order
+    pub indices: Vec<[SobolIndex; 3]>,
+    /// Output variance per metric
+    pub variance: [f64; 3],
+    pub runs: usize,
+}
+
+impl SensitivityReport {
+    pub fn index(&self, factor: usize, metric: Metric) -> SobolIndex {
+        let m = SENSITIVITY_METRICS.iter().position(|&x| x == metric).unwrap_or(0);
+        self.indices[factor][m]
+    }
+}
+
+/// Sobol indices of `factors` with `samples` base samples. `seed` fixes the
+/// sample matrices so a study can be repeated; `threads` caps the workers
+/// (0 = all cores).
+pub fn sobol_analysis(
+    config: &SimulationConfig,
+    inputs: &SimulationInputs,
+    factors: &[Factor],
+    samples: usize,
+    target_size: i64,
+    seed: u64,
+    threads: usize,
+) -> SensitivityReport {
+    let k = factors.len();
+    let mut rng = Rng::new(seed.max(1));
+    let a: Vec<Vec<f64>> = (0..samples).map(|_| (0..k).map(|_| rng.next_f64()).collect()).collect();
+    let b: Vec<Vec<f64>> = (0..samples).map(|_| (0..k).map(|_| rng.next_f64()).collect()).collect();
+
+    // Configs for A, B and then one AB matrix per factor, run as one batch
+    let configure = |point: &[f64]| -> SimulationConfig {
+        let mut run_config = *config;
+        for (factor, &u) in factors.iter().zip(point) {
+            factor.param.apply(&mut run_config, factor.value(u));
+        }
+        run_config
+    };
+    let mut configs: Vec<SimulationConfig> = a.iter().chain(&b).map(|row| configure(row)).collect();
+    for i in 0..k {
+        for (row_a, row_b) in a.iter().zip(&b) {
+            let mut row = row_a.clone();
+            row[i] = row_b[i];
+            configs.push(configure(&row));
+        }
+    }
+    let outputs: Vec<[f64; 3]> = run_totals(&configs, inputs, threads, target_size)
+        .iter()
+        .map(|totals| SENSITIVITY_METRICS.map(|metric| metric.of_totals(totals)))
+        .collect();
+    let (f_a, rest) = outputs.split_at(samples);
+    let (f_b, f_ab_all) = rest.split_at(samples);
+
+    let mut mean = [0.0; 3];
+    let mut variance = [0.0; 3];
+    for m in 0..3 {
+        let all: Vec<f64> = f_a.iter().chain(f_b).map(|y| y[m]).collect();
+        mean[m] = all.iter().sum::<f64>() / all.len().max(1) as f64;
+        variance[m] = all.iter().map(|y| (y - mean[m]).powi(2)).sum::<f64>() / all.len().max(1) as f64;
+    }
+
+    let mut indices = Vec::with_capacity(k);
+    for i in 0..k {
+        let f_ab = &f_ab_all[i * samples..(i + 1) * samples];
+
+        let mut factor_indices = [SobolIndex::default(); 3];
+        for (m, index) in factor_indices.iter_mut().enumerate() {
+            if variance[m] <= 0.0 {
+                continue;
+            }
+            // f_B is centred first: the expectation is unchanged, but an
+            // uncentred f_B scales the estimator's noise by the metric's mean
+            let n = samples.max(1) as f64;
+            let first: f64 =
+                (0..samples).map(|j| (f_b[j][m] - mean[m]) * (f_ab[j][m] - f_a[j][m])).sum::<f64>() / n;
+            let total: f64 = (0..samples).map(|j| (f_a[j][m] - f_ab[j][m]).powi(2)).sum::<f64>() / (2.0 * n);
+            *index = SobolIndex { first_order: first / variance[m], total_order: total / variance[m] };
+        }
+        indices.push(factor_indices);
+    }
+
+    SensitivityReport {
+        factors: factors.to_vec(),
+        indices,
+        variance,
+        runs: samples * (k + 2),
+    }
+}
@@ -0,0 +1,102 @@
+//! Headline numbers of a run, derived from its per-block series: how fast
+//! blocks grew, how long backlogs took to clear and what they cost
+
+use crate::constraints::BlockLimit;
+use crate::lib_core::{SimulationConfig, SimulationResults};
+
+/// Multiples of the steady state timed by default
+pub const STEADY_STATE_MULTIPLES: [i64; 3] = [2, 5, 10];
+
+/// First height at which M_B reached `multiple` times the steady state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeToSize {
+    pub multiple: i64,
+    pub blocks: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    pub time_to_size: Vec<TimeToSize>,
+    /// Height of the largest mempool (the first, if it peaked more than once)
+    pub mempool_peak_at: u32,
+    /// Blocks from the mempool peak until it was next empty; None if it was
+    /// still backlogged at the end of the run
+    pub mempool_recovery: Option<u32>,
+    /// Highest fee per byte paid for a block over the run's median fee per
+    /// byte (0 when no fees were paid)
+    pub fee_spike_ratio: f64,
+    /// Longest run of consecutive blocks that reached M_B_max, and the
+    /// height it started at
+    pub longest_full_span: u32,
+    pub longest_full_span_start: u32,
+    /// Sum of the mempool size over all blocks, in byte-blocks
+    pub backlog_area: f64,
+}
+
+impl RunSummary {
+    pub fn from_results(results: &SimulationResults, config: &SimulationConfig) -> RunSummary {
+        let height = |k: usize| results.indices.get(k).copied().unwrap_or(k as u32);
+
+        let time_to_size = STEADY_STATE_MULTIPLES
+            .iter()
+            .map(|&multiple| TimeToSize {
+                multiple,
+                blocks: results
+                    .M_B
+                    .iter()
+                    .position(|&m_b| m_b >= multiple * config.steady_state)
+                    .map(height),
+            })
+            .collect();
+
+        let peak = results
+            .mempool_size
+            .iter()
+            .enumerate()
+            .rev() // earliest of equal peaks
+            .max_by_key(|(_, &size)| size)
+            .map_or(0, |(k, _)| k);
+        let mempool_recovery = results.mempool_size[peak..]
+            .iter()
+            .position(|&size| size == 0)
+            .map(|k| height(peak + k) - height(peak));
+
+        let mut fee_per_byte: Vec<f64> = results
+            .block_fee
+            .iter()
+            .zip(&results.M_B)
+            .filter(|(_, &m_b)| m_b > 0)
+            .map(|(&fee, &m_b)| fee / m_b as f64)
+            .collect();
+        fee_per_byte.sort_by(f64::total_cmp);
+        let fee_spike_ratio = match (fee_per_byte.get(fee_per_byte.len() / 2), fee_per_byte.last()) {
+            (Some(&median), Some(&peak)) if median > 0.0 => peak / median,
+            _ => 0.0,
+        };
+
+        let mut longest_full_span = 0;
+        let mut longest_full_span_start = 0;
+        let mut span = 0;
+        for (k, &limit) in results.block_limit.iter().enumerate() {
+            if limit < BlockLimit::Fee as u8 {
+                span += 1;
+                if span > longest_full_span {
+                    longest_full_span = span;
+                    longest_full_span_start = height(k + 1 - span as usize);
+                }
+            } else {
+                span = 0;
+            }
+        }
+
+        RunSummary {
+            time_to_size,
+            mempool_peak_at: height(peak),
+            mempool_recovery,
+            fee_spike_ratio,
+            longest_full_span,
+            longest_full_span_start,
+            backlog_area: results.mempool_size.iter().map(|&size| size as f64).sum(),
+        }
+    }
+}
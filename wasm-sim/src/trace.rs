@@ -0,0 +1,76 @@
+//! Opt-in full trace of the per-block intermediates, mirroring the archives
+//! the Python original keeps (`M_L_weight`, `M_S_weight`, `F_T`, `blockfilled`, ...)
+
+/// Intermediate values for every block, recorded when `full_trace` is set
+#[derive(Clone, Debug, Default)]
+pub struct FullTrace {
+    /// Weight pushed into the long term window after clamping
+    pub m_l_weight: Vec<i64>,
+    /// Weight pushed into the short term window
+    pub m_s_weight: Vec<i64>,
+    pub m_b_max: Vec<i64>,
+    /// Reference fee per byte
+    pub f_r: Vec<f64>,
+    /// Marginal cost (penalty plus orphan risk) of the last tx the block
+    /// builder considered; 0 in simple block mode
+    pub last_f_t: Vec<f64>,
+    /// Transactions left in each tier after the block (high, low)
+    pub mempool_high: Vec<i64>,
+    pub mempool_low: Vec<i64>,
+    /// Tier and tx index where block building stopped; 0 in simple block mode
+    pub blockfilled_tier: Vec<i64>,
+    pub blockfilled_count: Vec<i64>,
+    /// Percent of new low-tier tx moved to the high tier by fee behavior
+    pub percent_response: Vec<f64>,
+}
+
+/// One block's intermediates
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceRow {
+    pub m_l_weight: i64,
+    pub m_s_weight: i64,
+    pub m_b_max: i64,
+    pub f_r: f64,
+    pub last_f_t: f64,
+    pub mempool: [i64; 2],
+    pub blockfilled: [i64; 2],
+    pub percent_response: f64,
+}
+
+impl FullTrace {
+    pub fn with_capacity(capacity: usize) -> Self {
+        FullTrace {
+            m_l_weight: Vec::with_capacity(capacity),
+            m_s_weight: Vec::with_capacity(capacity),
+            m_b_max: Vec::with_capacity(capacity),
+            f_r: Vec::with_capacity(capacity),
+            last_f_t: Vec::with_capacity(capacity),
+            mempool_high: Vec::with_capacity(capacity),
+            mempool_low: Vec::with_capacity(capacity),
+            blockfilled_tier: Vec::with_capacity(capacity),
+            blockfilled_count: Vec::with_capacity(capacity),
+            percent_response: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, row: TraceRow) {
+        self.m_l_weight.push(row.m_l_weight);
+        self.m_s_weight.push(row.m_s_weight);
+        self.m_b_max.push(row.m_b_max);
+        self.f_r.push(row.f_r);
+        self.last_f_t.push(row.last_f_t);
+        self.mempool_high.push(row.mempool[0]);
+        self.mempool_low.push(row.mempool[1]);
+        self.blockfilled_tier.push(row.blockfilled[0]);
+        self.blockfilled_count.push(row.blockfilled[1]);
+        self.percent_response.push(row.percent_response);
+    }
+
+    pub fn len(&self) -> usize {
+        self.m_b_max.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.m_b_max.is_empty()
+    }
+}
@@ -0,0 +1,307 @@
+//! Live terminal dashboard for native runs
+//!
+//! Hooks into the engine through `run_simulation_observed` and redraws
+//! sparklines of the run so far with ANSI escapes. Keys: space pauses,
+//! `.` steps one block while paused, `+`/`-` change speed, `g` followed by a
+//! height and Enter jumps to that block, `q` ends the run. Keys are read in
+//! raw mode set up with `stty`, so they only work on a Unix terminal; the
+//! dashboard still draws elsewhere.
+
+use wasm_sim::lib_core::BlockSnapshot;
+use std::io::{Read, Write};
+use std::ops::ControlFlow;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+/// Blocks run per frame at each speed setting; the last one is unthrottled
+const SPEEDS: [u32; 6] = [1, 10, 100, 1_000, 10_000, u32::MAX];
+
+const FRAME: Duration = Duration::from_millis(50);
+
+const SPARK_WIDTH: usize = 60;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Rows of the dashboard, in drawing order
+const ROWS: [&str; 6] = ["M_B", "M_L", "M_S", "M_N", "Mempool", "Fees"];
+
+/// Puts the terminal in raw mode for the lifetime of the value
+struct RawTerminal {
+    saved: Option<String>,
+}
+
+impl RawTerminal {
+    fn enter() -> RawTerminal {
+        let stty = |args: &[&str]| {
+            Command::new("stty")
+                .args(args)
+                .stdin(Stdio::inherit())
+                .stderr(Stdio::null())
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+        };
+        let saved = stty(&["-g"]).map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+        if saved.is_some() {
+            stty(&["-icanon", "-echo", "min", "1"]);
+        }
+        print!("\x1b[?25l\x1b[2J");
+        RawTerminal { saved }
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.saved {
+            let _ = Command::new("stty").arg(saved).stdin(Stdio::inherit()).status();
+        }
+        print!("\x1b[?25h");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Key presses, read on a separate thread so the run never waits on input
+fn spawn_key_reader() -> Receiver<u8> {
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        let mut byte = [0u8; 1];
+        while let Ok(1) = std::io::stdin().read(&mut byte) {
+            if tx.send(byte[0]).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+pub struct Dashboard {
+    n: u32,
+    /// Per-block values of each row
+    series: [Vec<f64>; 6],
+    last: BlockSnapshot,
+    paused: bool,
+    /// Index into `SPEEDS`
+    speed: usize,
+    /// Run unthrottled to this height, then pause
+    jump_to: Option<u32>,
+    /// Digits typed after `g`
+    typing: Option<String>,
+    /// Run one more block, then pause again
+    step: bool,
+    blocks_this_frame: u32,
+    frame_start: Instant,
+    keys: Receiver<u8>,
+    quit: bool,
+    _terminal: RawTerminal,
+}
+
+impl Dashboard {
+    /// Take over the terminal for a run of `n` blocks
+    pub fn new(n: u32) -> Dashboard {
+        Dashboard {
+            n,
+            series: Default::default(),
+            last: BlockSnapshot::default(),
+            paused: false,
+            speed: 2,
+            jump_to: None,
+            typing: None,
+            step: false,
+            blocks_this_frame: 0,
+            frame_start: Instant::now(),
+            keys: spawn_key_reader(),
+            quit: false,
+            _terminal: RawTerminal::enter(),
+        }
+    }
+
+    /// Record a block, then draw and wait as the speed and pause state ask
+    pub fn observe(&mut self, block: &BlockSnapshot) -> ControlFlow<()> {
+        let values = [
+            block.m_b as f64,
+            block.m_l as f64,
+            block.m_s as f64,
+            block.m_n as f64,
+            block.mempool as f64,
+            block.block_fee,
+        ];
+        for (series, value) in self.series.iter_mut().zip(values) {
+            series.push(value);
+        }
+        self.last = *block;
+        self.blocks_this_frame += 1;
+
+        if self.jump_to.is_some_and(|target| block.height >= target) {
+            self.jump_to = None;
+            self.paused = true;
+        }
+        self.handle_keys();
+
+        let jumping = self.jump_to.is_some();
+        if !self.paused && (jumping || self.blocks_this_frame < SPEEDS[self.speed]) {
+            // Mid-frame: only redraw when a frame's worth of time has passed
+            if self.frame_start.elapsed() >= FRAME {
+                self.draw();
+                self.next_frame();
+            }
+        } else {
+            self.draw();
+            let remaining = FRAME.saturating_sub(self.frame_start.elapsed());
+            std::thread::sleep(remaining);
+            self.next_frame();
+            while self.paused && !self.quit {
+                if self.handle_keys_until(FRAME) {
+                    self.draw();
+                }
+                if self.step {
+                    self.step = false;
+                    break;
+                }
+            }
+        }
+
+        if self.quit { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }
+
+    /// Draw the final state and hand the terminal back
+    pub fn finish(self) {
+        self.draw();
+        println!();
+    }
+
+    fn next_frame(&mut self) {
+        self.blocks_this_frame = 0;
+        self.frame_start = Instant::now();
+    }
+
+    fn handle_keys(&mut self) {
+        while let Ok(key) = self.keys.try_recv() {
+            self.key(key);
+        }
+    }
+
+    /// Wait up to `timeout` for a key; true if one arrived
+    fn handle_keys_until(&mut self, timeout: Duration) -> bool {
+        match self.keys.recv_timeout(timeout) {
+            Ok(key) => {
+                self.key(key);
+                self.handle_keys();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn key(&mut self, key: u8) {
+        if let Some(typed) = self.typing.as_mut() {
+            match key {
+                b'0'..=b'9' => typed.push(key as char),
+                0x7f | 0x08 => {
+                    typed.pop();
+                }
+                b'\n' | b'\r' => {
+                    let target = typed.parse::<u32>().ok();
+                    self.typing = None;
+                    if let Some(target) = target.filter(|&t| t > self.last.height) {
+                        self.jump_to = Some(target.min(self.n.saturating_sub(1)));
+                        self.paused = false;
+                    }
+                }
+                0x1b => self.typing = None,
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            b' ' | b'p' => self.paused = !self.paused,
+            b'.' if self.paused => self.step = true,
+            b'+' | b'=' => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            b'-' | b'_' => self.speed = self.speed.saturating_sub(1),
+            b'g' => self.typing = Some(String::new()),
+            b'q' => self.quit = true,
+            _ => {}
+        }
+    }
+
+    fn draw(&self) {
+        let mut out = String::from("\x1b[H");
+        let line = |out: &mut String, text: String| {
+            out.push_str(&text);
+            out.push_str("\x1b[K\n");
+        };
+        let done = self.last.height + 1;
+        line(&mut out, format!("Block {} / {} ({:.1}%)", done, self.n, done as f64 * 100.0 / self.n.max(1) as f64));
+        line(&mut out, String::new());
+        for (name, series) in ROWS.iter().zip(&self.series) {
+            let current = series.last().copied().unwrap_or(0.0);
+            let peak = series.iter().copied().fold(0.0, f64::max);
+            line(
+                &mut out,
+                format!("{:<8} {} {:>12} (max {})", name, sparkline(series, SPARK_WIDTH), format_value(current), format_value(peak)),
+            );
+        }
+        line(&mut out, String::new());
+        line(
+            &mut out,
+            format!(
+                "M_B_max {}   Penalty {:.3e}   Broadcast {}",
+                format_value(self.last.m_b_max as f64),
+                self.last.penalty,
+                format_value(self.last.input_volume as f64)
+            ),
+        );
+        let speed = match SPEEDS[self.speed] {
+            u32::MAX => "max".to_string(),
+            blocks => format!("{} blocks/frame", blocks),
+        };
+        let state = if let Some(typed) = &self.typing {
+            format!("Jump to block: {}_", typed)
+        } else if let Some(target) = self.jump_to {
+            format!("Jumping to block {}", target)
+        } else if self.paused {
+            "Paused".to_string()
+        } else {
+            "Running".to_string()
+        };
+        line(&mut out, format!("{} | speed {}", state, speed));
+        line(&mut out, "[space] pause  [.] step  [+/-] speed  [g] jump to block  [q] quit".to_string());
+        print!("{}", out);
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// The whole series squeezed into `width` columns, each the max of its
+/// blocks, scaled between the series' min and max
+fn sparkline(series: &[f64], width: usize) -> String {
+    let low = series.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = series.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let columns = width.min(series.len());
+    let mut line: String = (0..columns)
+        .map(|c| {
+            let from = c * series.len() / columns;
+            let to = ((c + 1) * series.len() / columns).max(from + 1);
+            let value = series[from..to].iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let level = if high > low { ((value - low) / (high - low) * (SPARKS.len() - 1) as f64).round() as usize } else { 0 };
+            SPARKS[level.min(SPARKS.len() - 1)]
+        })
+        .collect();
+    line.extend(std::iter::repeat_n(' ', width - columns));
+    line
+}
+
+/// Compact number: 1.25M, 300k, 0.0123
+fn format_value(v: f64) -> String {
+    let a = v.abs();
+    if a >= 1e9 {
+        format!("{:.2}G", v / 1e9)
+    } else if a >= 1e6 {
+        format!("{:.2}M", v / 1e6)
+    } else if a >= 1e3 {
+        format!("{:.1}k", v / 1e3)
+    } else if a >= 0.01 || a == 0.0 {
+        format!("{:.3}", v)
+    } else {
+        format!("{:.2e}", v)
+    }
+}
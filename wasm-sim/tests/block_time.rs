@@ -0,0 +1,60 @@
+//! Block time scaling of the demand curves and configurable median windows
+
+use wasm_sim::lib_core::{run_simulation_core, SimulationConfig};
+
+fn run(block_time: u32, hours: u32, run_type: u32) -> Vec<i64> {
+    let config = SimulationConfig {
+        n: hours * 3600 / block_time,
+        run_type,
+        block_time,
+        simple_blocks: true,
+        ..SimulationConfig::default()
+    };
+    run_simulation_core(config).input_volume
+}
+
+#[test]
+fn demand_follows_wall_clock_time() {
+    for run_type in [1, 3, 6] {
+        let reference = run(120, 48, run_type);
+        let fast = run(60, 48, run_type);
+        assert_eq!(fast.len(), 2 * reference.len());
+
+        // Same bytes per second over the run
+        let total = |volume: &[i64]| volume.iter().sum::<i64>() as f64;
+        let ratio = total(&fast) / total(&reference);
+        assert!((ratio - 1.0).abs() < 0.01, "run type {}: {}", run_type, ratio);
+
+        // and half the bytes per block at the same time of day
+        for (i, &volume) in reference.iter().enumerate().step_by(100) {
+            let half = fast[2 * i] as f64 / volume as f64;
+            assert!((half - 0.5).abs() < 0.01, "run type {} block {}: {}", run_type, i, half);
+        }
+    }
+}
+
+#[test]
+fn odd_windows_keep_their_length() {
+    let config = SimulationConfig {
+        n: 3000,
+        run_type: 1,
+        long_window: 1001,
+        short_window: 51,
+        exact_median: true,
+        simple_blocks: true,
+        ..SimulationConfig::default()
+    };
+    assert_eq!((config.long_window_len(), config.short_window_len()), (1001, 51));
+    let results = run_simulation_core(config);
+    let windows = &results.final_windows;
+    assert_eq!((windows.long_weights.len(), windows.short_weights.len()), (1001, 51));
+
+    // Odd windows have a middle element
+    let median = |weights: &[i64]| {
+        let mut sorted = weights.to_vec();
+        sorted.sort_unstable();
+        sorted[sorted.len() / 2]
+    };
+    assert_eq!(*results.M_L.last().unwrap(), median(&windows.long_weights));
+    assert_eq!(*results.M_S.last().unwrap(), median(&windows.short_weights));
+}
@@ -0,0 +1,51 @@
+//! SVG charts: framing and one path per series, on linear and log axes
+
+use wasm_sim::chart::{ChartKind, ChartScales};
+use wasm_sim::lib_core::{run_simulation_core, SimulationConfig, SimulationResults};
+
+fn check_svg(results: &SimulationResults, kind: ChartKind, log_scale: bool) {
+    let chart = kind.chart(results, &ChartScales::default(), log_scale);
+    let svg = chart.to_svg();
+    let context = format!("{} (log {})", kind.name(), log_scale);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""), "{}", context);
+    assert!(svg.ends_with("</svg>\n"), "{}", context);
+    assert_eq!(svg.matches("<svg").count(), 1, "{}", context);
+    assert_eq!(svg.matches("<path ").count(), chart.series.len(), "{}", context);
+    assert!(!svg.contains("NaN") && !svg.contains("inf"), "{}", context);
+}
+
+#[test]
+fn renders_every_chart_of_a_run() {
+    let config = SimulationConfig { n: 2000, run_type: 2, ..SimulationConfig::default() };
+    let results = run_simulation_core(config);
+    for kind in ChartKind::ALL {
+        for log_scale in [false, true] {
+            check_svg(&results, kind, log_scale);
+        }
+    }
+}
+
+#[test]
+fn renders_empty_and_all_zero_series() {
+    // Axes fall back to a default range when there is nothing to fit
+    let empty = SimulationResults::default();
+    let zeros = SimulationResults {
+        M_B: vec![0; 50],
+        M_L: vec![0; 50],
+        M_S: vec![0; 50],
+        M_N: vec![0; 50],
+        input_volume: vec![0; 50],
+        mempool_size: vec![0; 50],
+        block_fee: vec![0.0; 50],
+        penalty: vec![0.0; 50],
+        indices: (0..50).collect(),
+        ..SimulationResults::default()
+    };
+    for results in [&empty, &zeros] {
+        for kind in ChartKind::ALL {
+            for log_scale in [false, true] {
+                check_svg(results, kind, log_scale);
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! A/B comparison through the WASM wrapper, which shares the page's inputs
+//! with plain runs
+
+use wasm_sim::{
+    clear_window_seed, get_compare_b_m_b_ptr, get_m_b_ptr, get_result_len, run_comparison, run_simulation,
+    set_window_seed, SimulationConfig,
+};
+
+fn config() -> SimulationConfig {
+    SimulationConfig::new(
+        500, 1_000_000, 1_000_000, 10_000, 0.6, 50_000, 50, 800, 5, 3.0, 2.0, 50.0, false, false, false, false, false,
+        10_000_000, 0.0, true, 10_000_000, 0,
+    )
+}
+
+fn series(ptr: *const i64) -> Vec<i64> {
+    unsafe { std::slice::from_raw_parts(ptr, get_result_len() as usize) }.to_vec()
+}
+
+// One test, since the wrapper keeps its buffers and window seed in globals
+#[test]
+fn comparison_starts_from_the_window_seed() {
+    set_window_seed(vec![3_000_000; 100_000], vec![3_000_000; 100], 3_000_000);
+    run_simulation(config());
+    let seeded = series(get_m_b_ptr());
+    assert!(seeded[0] > 2_000_000);
+
+    run_comparison(config(), config());
+    assert_eq!(series(get_m_b_ptr()), seeded);
+    assert_eq!(series(get_compare_b_m_b_ptr()), seeded);
+
+    clear_window_seed();
+    run_comparison(config(), config());
+    assert!(series(get_m_b_ptr())[0] < 2_000_000);
+}
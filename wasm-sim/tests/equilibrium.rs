@@ -0,0 +1,71 @@
+//! Analytic equilibrium against the engine at constant demand
+
+use wasm_sim::equilibrium::{Equilibrium, CONVERGENCE_TOLERANCE};
+use wasm_sim::lib_core::{run_simulation_with_inputs, SimulationConfig, SimulationInputs, SimulationResults};
+
+/// Short windows so runs reach equilibrium in a few thousand blocks
+fn config(exact_median: bool) -> SimulationConfig {
+    SimulationConfig { long_window: 2000, short_window: 50, exact_median, ..SimulationConfig::default() }
+}
+
+/// Engine run at `eq.demand` lasting three long windows past the solver's
+/// convergence height
+fn run(config: SimulationConfig, eq: &Equilibrium) -> SimulationResults {
+    let n = eq.converge_blocks.expect("solver converges") + 3 * config.long_window;
+    let inputs = SimulationInputs { demand_trace: Some(vec![eq.demand]), ..SimulationInputs::default() };
+    run_simulation_with_inputs(SimulationConfig { n, ..config }, &inputs)
+}
+
+/// First height from which M_B, M_S and M_L stay within tolerance of `eq`
+fn settled_at(results: &SimulationResults, eq: &Equilibrium) -> usize {
+    let near = |value: i64, target: i64| (value - target).abs() as f64 <= CONVERGENCE_TOLERANCE * target as f64;
+    (0..results.M_B.len())
+        .rposition(|i| !(near(results.M_B[i], eq.m_b) && near(results.M_S[i], eq.m_s) && near(results.M_L[i], eq.m_l)))
+        .map_or(0, |i| i + 1)
+}
+
+#[test]
+fn solver_matches_the_engine() {
+    for exact_median in [false, true] {
+        for demand in [800_000, 1_200_000, 2_000_000] {
+            let config = config(exact_median);
+            let eq = Equilibrium::solve(&config, demand);
+            assert_eq!((eq.m_b, eq.m_l, eq.m_s), (demand, demand.max(config.z_m), demand.max(config.z_m)));
+
+            let results = run(config, &eq);
+            let last = |series: &[i64]| *series.last().unwrap();
+            assert_eq!((last(&results.M_B), last(&results.M_L), last(&results.M_S)), (eq.m_b, eq.m_l, eq.m_s));
+            assert_eq!(Some(settled_at(&results, &eq) as u32), eq.converge_blocks, "demand {}", demand);
+        }
+    }
+}
+
+#[test]
+fn refresh_interval_sets_the_convergence_height() {
+    // M_L only moves when the long window is re-sorted, which takes longer
+    // than following it every block
+    let periodic = Equilibrium::solve(&config(false), 2_000_000).converge_blocks.unwrap();
+    let exact = Equilibrium::solve(&config(true), 2_000_000).converge_blocks.unwrap();
+    assert!(periodic > exact);
+}
+
+#[test]
+fn blocks_stay_at_the_start_without_fee_headroom() {
+    // With t_r < t_sim the low-tier fee never covers a tx past M_N
+    let config = SimulationConfig { t_r: 400, ..config(true) };
+    let eq = Equilibrium::solve(&config, 2_000_000);
+    assert_eq!((eq.m_b, eq.m_l, eq.m_s), (config.steady_state, config.steady_state, config.steady_state));
+    assert_eq!(eq.backlog_growth, 2_000_000 - config.steady_state);
+
+    let results = run(config, &eq);
+    assert_eq!(*results.M_B.iter().max().unwrap(), config.steady_state);
+}
+
+#[test]
+fn simple_blocks_fill_to_the_limit() {
+    let config = SimulationConfig { simple_blocks: true, ..config(true) };
+    let eq = Equilibrium::solve(&config, 2_000_000);
+    assert_eq!(eq.m_b, 2_000_000);
+    let results = run(config, &eq);
+    assert_eq!(Some(settled_at(&results, &eq) as u32), eq.converge_blocks);
+}
@@ -0,0 +1,118 @@
+//! Scripted event timelines: scenario parsing and the shocks a cursor applies
+
+use wasm_sim::events::{EventAction, EventTimeline, Param, ScheduledEvent, TimelineCursor};
+use wasm_sim::fork::WindowTransition;
+use wasm_sim::lib_core::{run_simulation_with_inputs, RuleSet, SimulationConfig, SimulationInputs};
+
+#[test]
+fn parses_scenario_files() {
+    let text = "# height  event   arguments\n\
+                \n\
+                10000     rules   legacy reset  # hard fork\n\
+                5000      demand  10 720\n\
+                6000\tspam\t2000000 1440\n\
+                8000      set     r_base 0.3\n\
+                9000      rules   current\n";
+    let timeline = EventTimeline::parse(text).unwrap();
+    // Events come back ordered by height
+    assert_eq!(
+        timeline.events,
+        vec![
+            ScheduledEvent { height: 5000, action: EventAction::Demand { factor: 10.0, duration: 720 } },
+            ScheduledEvent { height: 6000, action: EventAction::Spam { bytes: 2_000_000, duration: 1440 } },
+            ScheduledEvent { height: 8000, action: EventAction::Set { param: Param::RBase, value: 0.3 } },
+            ScheduledEvent { height: 9000, action: EventAction::Rules { rules: RuleSet::Current, windows: None } },
+            ScheduledEvent {
+                height: 10000,
+                action: EventAction::Rules { rules: RuleSet::Legacy, windows: Some(WindowTransition::Reset) },
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_bad_lines() {
+    let cases = [
+        ("5000\n", "line 1: expected height and event"),
+        ("# shock\nabc demand 2 10\n", "line 2: invalid height"),
+        ("5000 demand 2\n", "line 1: missing argument"),
+        ("5000 spam lots 10\n", "line 1: invalid number"),
+        ("5000 set block_size 2\n", "line 1: unknown parameter"),
+        ("5000 rules newest\n", "line 1: unknown rule set"),
+        ("5000 rules legacy keep\n", "line 1: unknown window transition"),
+        ("5000 quake 7\n", "line 1: unknown event 'quake'"),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(EventTimeline::parse(text).unwrap_err(), expected, "{:?}", text);
+    }
+}
+
+#[test]
+fn cursor_applies_shocks_for_their_duration() {
+    let timeline = EventTimeline::parse("10 demand 2 3\n11 spam 500 1\n12 set z_m 1000\n").unwrap();
+    let mut cursor = TimelineCursor::new(Some(&timeline));
+    let mut config = SimulationConfig::default();
+
+    let mut seen = Vec::new();
+    for height in 9..15 {
+        assert_eq!(cursor.advance(height, &mut config), None);
+        seen.push((cursor.demand_factor(), cursor.spam_bytes()));
+    }
+    assert_eq!(seen, vec![(1.0, 0), (2.0, 0), (2.0, 500), (2.0, 0), (1.0, 0), (1.0, 0)]);
+    assert_eq!(config.z_m, 1000);
+}
+
+#[test]
+fn shocks_near_the_last_height_do_not_overflow() {
+    let timeline = EventTimeline::parse(&format!("{} demand 3 {}\n", u32::MAX - 1, u32::MAX)).unwrap();
+    let mut cursor = TimelineCursor::new(Some(&timeline));
+    let mut config = SimulationConfig::default();
+    cursor.advance(u32::MAX - 1, &mut config);
+    assert_eq!(cursor.demand_factor(), 3.0);
+    cursor.advance(u32::MAX, &mut config);
+    assert_eq!(cursor.demand_factor(), 3.0);
+}
+
+#[test]
+fn rule_switch_reports_its_window_transition() {
+    let timeline = EventTimeline::parse("5 rules legacy reclamp\n8 rules current\n").unwrap();
+    let mut cursor = TimelineCursor::new(Some(&timeline));
+    let mut config = SimulationConfig { fork_windows: WindowTransition::Carry, ..SimulationConfig::default() };
+    assert_eq!(cursor.advance(5, &mut config), Some(WindowTransition::Reclamp));
+    assert_eq!(config.rule_set, RuleSet::Legacy);
+    assert_eq!(cursor.advance(8, &mut config), Some(WindowTransition::Carry));
+    assert_eq!(config.rule_set, RuleSet::Current);
+}
+
+#[test]
+fn fork_at_the_first_block_is_measured() {
+    let config = SimulationConfig { n: 2000, run_type: 5, ..SimulationConfig::default() };
+    let inputs = SimulationInputs {
+        events: Some(EventTimeline::parse("0 rules legacy\n").unwrap()),
+        ..SimulationInputs::default()
+    };
+    let results = run_simulation_with_inputs(config, &inputs);
+    let fork = &results.forks[0];
+    assert_eq!((fork.height, fork.from, fork.to), (0, RuleSet::Current, RuleSet::Legacy));
+    // Measured against the old rules on the starting windows
+    assert_eq!(fork.m_n_before, config.steady_state);
+    assert_eq!(fork.m_b_max_before, 2 * config.steady_state);
+    assert!(fork.peak_m_n_deviation > 0.0);
+}
+
+#[test]
+fn fee_parameter_events_reprice_elastic_demand() {
+    let config = SimulationConfig { n: 3000, run_type: 2, demand_elasticity: 1.0, ..SimulationConfig::default() };
+    let inputs = SimulationInputs {
+        events: Some(EventTimeline::parse("0 set r_base 0.3\n0 set t_r 5000\n").unwrap()),
+        ..SimulationInputs::default()
+    };
+    // Demand is priced against the new fee level, as if the run had started with it
+    let scripted = run_simulation_with_inputs(config, &inputs);
+    let preset = run_simulation_with_inputs(
+        SimulationConfig { r_base: 0.3, t_r: 5000, ..config },
+        &SimulationInputs::default(),
+    );
+    assert_eq!(scripted.input_volume, preset.input_volume);
+    assert_eq!(scripted.M_B, preset.M_B);
+}
@@ -0,0 +1,57 @@
+#!/usr/bin/env python3
+"""
+Regenerate the Python parity fixtures used by tests/python_parity.rs.
+
+Runs the reference model from compare_implementations.py (the original
+script with its parameters made configurable) and writes one CSV per
+scenario. numpy is only imported for the comparison step there, so it is
+stubbed out and the fixtures can be rebuilt with a plain Python install.
+
+Usage (from the repository root): python3 wasm-sim/tests/fixtures/python_parity/generate.py
+"""
+
+import os
+import sys
+import types
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+ROOT = os.path.abspath(os.path.join(HERE, "..", "..", "..", ".."))
+sys.path.insert(0, ROOT)
+sys.modules.setdefault("numpy", types.ModuleType("numpy"))
+
+from compare_implementations import run_python_simulation  # noqa: E402
+
+SCENARIOS = [
+    {"name": "linear_ramp", "n": 1000, "run_type": 1, "large_sim_mode": False},
+    {"name": "fast_linear_ramp", "n": 2000, "run_type": 2, "large_sim_mode": True},
+    {"name": "parabolic_ramp", "n": 2000, "run_type": 3, "large_sim_mode": True},
+    {"name": "max_flood", "n": 1000, "run_type": 5, "large_sim_mode": False},
+    {"name": "ramp_to_sine", "n": 1500, "run_type": 6, "large_sim_mode": True},
+]
+
+COLUMNS = ["M_B", "M_L", "M_S", "M_N", "input_volume", "block_fee", "penalty"]
+
+
+def number(value):
+    if isinstance(value, float) and value.is_integer():
+        return str(int(value))
+    return repr(value)
+
+
+def main():
+    for scenario in SCENARIOS:
+        data = run_python_simulation(dict(scenario, exact_median=True))
+        if data is None:
+            sys.exit(1)
+        samples = data["sample_data"]
+        path = os.path.join(HERE, scenario["name"] + ".csv")
+        with open(path, "w") as out:
+            out.write("# n={} run_type={} large_sim_mode={}\n".format(
+                scenario["n"], scenario["run_type"], int(scenario["large_sim_mode"])))
+            out.write(",".join(COLUMNS) + "\n")
+            for row in zip(*(samples[c] for c in COLUMNS)):
+                out.write(",".join(number(v) for v in row) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,79 @@
+//! Chain history import: CSV parsing and the medians observed on the chain
+
+use wasm_sim::history::{BlockRecord, ChainHistory, ChainRules, MAINNET};
+
+#[test]
+fn parses_header_comments_and_separators() {
+    let text = "height,block_weight,num_txes\n\
+                # dumped from monerod\n\
+                \n\
+                3000000,120000,12\n\
+                3000001\t95000\t7\n\
+                3000002 300500  31\n";
+    let history = ChainHistory::from_csv_str(text).unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history.blocks[1],
+        BlockRecord { height: 3_000_001, weight: 95_000, tx_count: 7, long_term_weight: None }
+    );
+    assert_eq!(history.weights(), vec![120_000, 95_000, 300_500]);
+    let tx: Vec<u32> = history.blocks.iter().map(|b| b.tx_count).collect();
+    assert_eq!(tx, vec![12, 7, 31]);
+}
+
+#[test]
+fn optional_columns() {
+    let history = ChainHistory::from_csv_str("10,5000\n11,6000,3,5500\n").unwrap();
+    assert_eq!(history.blocks[0].tx_count, 0);
+    assert_eq!(history.blocks[0].long_term_weight, None);
+    assert_eq!(history.blocks[1].tx_count, 3);
+    assert_eq!(history.blocks[1].long_term_weight, Some(5500));
+}
+
+#[test]
+fn rejects_bad_rows() {
+    let cases = [
+        ("10,5000\n11\n", "line 2: expected height and block weight"),
+        ("10,5000\n11,abc\n", "line 2: invalid block weight 'abc'"),
+        ("10,5000\nx1,6000\n", "line 2: invalid height 'x1'"),
+        ("10,5000,-3\n", "line 1: invalid tx count '-3'"),
+        ("10,5000,2,big\n", "line 1: invalid long term weight 'big'"),
+        // A malformed first row is not mistaken for a header
+        ("x1,6000\n11,7000\n", "line 1: invalid height 'x1'"),
+        ("# dump\n10,abc\n", "line 2: invalid block weight 'abc'"),
+        ("height,block_weight\nheight,block_weight\n10,5000\n", "line 2: invalid height 'height'"),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(ChainHistory::from_csv_str(text).unwrap_err(), expected, "{:?}", text);
+    }
+}
+
+#[test]
+fn rejects_empty_history() {
+    for text in ["", "# nothing\n\n", "height,block_weight\n"] {
+        assert_eq!(ChainHistory::from_csv_str(text).unwrap_err(), "history contains no blocks");
+    }
+}
+
+#[test]
+fn observed_medians_follow_chain_rules() {
+    let rules = ChainRules { long_window: 5, short_window: 3, ..MAINNET };
+    // Below the full reward zone both medians sit at the floor
+    let quiet = ChainHistory::from_csv_str("1,100000\n2,120000\n3,90000\n").unwrap();
+    let observed = quiet.observed_medians(&rules);
+    assert_eq!(observed.m_l, vec![300_000; 3]);
+    assert_eq!(observed.m_s, vec![300_000; 3]);
+
+    // A surge moves M_S within one short window, while its long term weight
+    // is capped at 1.4 * M_L
+    let surge = ChainHistory::from_csv_str("1,400000\n2,2000000\n3,2000000\n4,2000000\n").unwrap();
+    let observed = surge.observed_medians(&rules);
+    assert_eq!(observed.m_b, vec![400_000, 2_000_000, 2_000_000, 2_000_000]);
+    assert_eq!(observed.m_s, vec![400_000, 400_000, 400_000, 2_000_000]);
+    let rules = ChainRules { long_window: 3, ..rules };
+    assert_eq!(surge.observed_medians(&rules).m_l, vec![400_000, 400_000, 400_000, 560_000]);
+
+    // Recorded long term weights are used as given
+    let recorded = ChainHistory::from_csv_str("1,400000,1,400000\n2,2000000,1,1000000\n3,2000000,1,1000000\n4,2000000,1,1000000\n").unwrap();
+    assert_eq!(recorded.observed_medians(&rules).m_l, vec![400_000, 400_000, 400_000, 1_000_000]);
+}
@@ -0,0 +1,70 @@
+//! The lockstep engine against the scalar engine: every simple-blocks config
+//! it runs must give the same totals as `run_simulation_with_inputs`.
+
+use wasm_sim::lib_core::{run_simulation_with_inputs, RuleSet, SimulationConfig, SimulationInputs};
+use wasm_sim::lockstep::{run_totals, supported, RunTotals, LANES};
+use wasm_sim::sanity::SanitySchedule;
+use wasm_sim::seed::WindowSeed;
+
+const TARGET_SIZE: i64 = 1_500_000;
+
+/// Simple-blocks configs over run types, rules, sanity schedules, noise and
+/// median modes, more than one chunk of lanes per window shape
+fn configs() -> Vec<SimulationConfig> {
+    let base = SimulationConfig {
+        n: 3000,
+        simple_blocks: true,
+        mid_100k: 1500,
+        ..SimulationConfig::default()
+    };
+    let mut configs = Vec::new();
+    for k in 0..3 * LANES {
+        let mut config = base;
+        config.run_type = 1 + (k % 6) as u32;
+        config.ml_mult = 1.4 + 0.1 * (k % 5) as f64;
+        config.ml_cap_mult = 4.0 + (k % 3) as f64 * 6.0;
+        config.rule_set = if k % 4 == 3 { RuleSet::Legacy } else { RuleSet::Current };
+        config.use_long_term_median_cap = k % 8 != 7;
+        config.sanity_schedule = match k % 5 {
+            0 => SanitySchedule::Linear,
+            1 => SanitySchedule::Stepwise,
+            2 => SanitySchedule::TiedToMl { multiple: 3.0 },
+            _ => SanitySchedule::Compound,
+        };
+        config.sanity_start_weight = 1_200_000 + 100_000 * (k % 4) as i64;
+        config.add_noise = k % 3 == 0;
+        config.seed = k as u64 % 4;
+        config.exact_median = k % 7 == 0;
+        configs.push(config);
+    }
+    configs
+}
+
+fn assert_parity(configs: &[SimulationConfig], inputs: &SimulationInputs) {
+    assert!(configs.iter().all(|config| supported(config, inputs)));
+    let lockstep = run_totals(configs, inputs, 0, TARGET_SIZE);
+    for (k, (config, totals)) in configs.iter().zip(&lockstep).enumerate() {
+        let scalar = RunTotals::from_results(&run_simulation_with_inputs(*config, inputs), TARGET_SIZE);
+        assert_eq!(*totals, scalar, "config {}", k);
+    }
+}
+
+#[test]
+fn matches_scalar_engine() {
+    assert_parity(&configs(), &SimulationInputs::default());
+}
+
+#[test]
+fn matches_scalar_engine_with_inputs() {
+    let weights: Vec<i64> = (0..4000).map(|h| 1_000_000 + 400 * h).collect();
+    let inputs = SimulationInputs {
+        demand_trace: Some((0..2000).map(|h| 900_000 + 1500 * h).collect()),
+        window_seed: Some(WindowSeed {
+            long_weights: weights.clone(),
+            short_weights: weights[weights.len() - 100..].to_vec(),
+            last_block_weight: 2_500_000,
+        }),
+        ..SimulationInputs::default()
+    };
+    assert_parity(&configs(), &inputs);
+}
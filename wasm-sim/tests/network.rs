@@ -0,0 +1,56 @@
+//! Multi-miner network: model setup and the orphan race
+
+use wasm_sim::lib_core::{run_simulation_with_inputs, SimulationConfig, SimulationInputs};
+use wasm_sim::network::NetworkModel;
+
+#[test]
+fn from_shares_needs_miners_with_hashrate() {
+    for shares in [&[][..], &[0.0, 0.0], &[-0.5, 1.5], &[f64::NAN, 1.0]] {
+        assert_eq!(
+            NetworkModel::from_shares(shares, 1e7, 0.5, 2e6).unwrap_err(),
+            "hashrate shares must be non-negative with a positive sum",
+            "{:?}",
+            shares
+        );
+    }
+    let model = NetworkModel::from_shares(&[3.0, 1.0], 1e7, 0.5, 2e6).unwrap();
+    let shares: Vec<f64> = model.miners.iter().map(|m| m.hashrate_share).collect();
+    assert_eq!(shares, vec![0.75, 0.25]);
+    assert_eq!((model.pick_miner(0.7), model.pick_miner(0.8)), (0, 1));
+    assert_eq!((model.pick_rival(0, 0.99), model.pick_rival(1, 0.0)), (1, 0));
+}
+
+#[test]
+fn empty_network_runs_without_one() {
+    let config = SimulationConfig { n: 500, ..SimulationConfig::default() };
+    let network = NetworkModel { miners: Vec::new(), validation_rate: 2e6, orphan_aware: true };
+    let inputs = SimulationInputs { network: Some(network), ..SimulationInputs::default() };
+    assert!(run_simulation_with_inputs(config, &inputs).network.is_none());
+}
+
+#[test]
+fn orphaned_blocks_are_re_mined() {
+    // Slow relay, so large blocks often lose the race. Simple blocks fill to
+    // M_B_max whatever the orphan risk.
+    let config = SimulationConfig {
+        n: 3000,
+        run_type: 5,
+        simple_blocks: true,
+        miners: 10,
+        miner_bandwidth: 200_000.0,
+        ..SimulationConfig::default()
+    };
+    let results = run_simulation_with_inputs(config, &SimulationInputs::default());
+    let network = results.network.as_ref().unwrap();
+
+    // One accepted block per height, plus the ones that lost the race
+    let orphans = network.orphaned_blocks.len();
+    assert!(orphans > 100 && results.max_mb > 0);
+    assert_eq!(network.block_miner.len(), 3000);
+    assert_eq!(network.miners.iter().map(|m| m.blocks_mined as usize).sum::<usize>(), 3000 + orphans);
+    assert_eq!(results.M_B.len(), 3000);
+
+    // The race reproduces the expected orphan probability on average
+    let expected = network.orphan_probability.iter().sum::<f64>() / 3000.0;
+    assert!((network.orphan_rate() - expected).abs() < 0.02, "{} vs {}", network.orphan_rate(), expected);
+}
@@ -0,0 +1,52 @@
+//! Goal-seeking search: setup validation and a small feasible search
+
+use wasm_sim::lib_core::{SimulationConfig, SimulationInputs};
+use wasm_sim::optimizer::{optimize, Bound, Constraint, Goal, Objective, Scenario, Sense};
+use wasm_sim::sensitivity::{Factor, Metric};
+
+fn scenario(name: &str) -> Scenario {
+    let config = SimulationConfig { n: 1000, run_type: 5, simple_blocks: true, ..SimulationConfig::default() };
+    Scenario { name: name.to_string(), config, inputs: SimulationInputs::default() }
+}
+
+fn goal(objective_scenario: usize, constraint_scenario: usize) -> Goal {
+    Goal {
+        objective: Objective { scenario: objective_scenario, metric: Metric::MaxMb, sense: Sense::Maximize },
+        constraints: vec![Constraint {
+            scenario: constraint_scenario,
+            metric: Metric::MaxMb,
+            bound: Bound::AtMost(1e12),
+        }],
+        target_size: 3_000_000,
+    }
+}
+
+#[test]
+fn rejects_goals_outside_the_scenarios() {
+    let factors = [Factor::parse("ml_mult:1.2:2.0").unwrap()];
+    assert_eq!(
+        optimize(&[], &factors, &goal(0, 0), 10, 1).unwrap_err(),
+        "the optimizer needs at least one scenario"
+    );
+    let scenarios = [scenario("base")];
+    assert_eq!(
+        optimize(&scenarios, &factors, &goal(1, 0), 10, 1).unwrap_err(),
+        "goal refers to scenario 1 but there are only 1"
+    );
+    assert_eq!(
+        optimize(&scenarios, &factors, &goal(0, 3), 10, 1).unwrap_err(),
+        "goal refers to scenario 3 but there are only 1"
+    );
+}
+
+#[test]
+fn best_candidate_matches_its_runs() {
+    let scenarios = [scenario("base"), scenario("flood")];
+    let factors = [Factor::parse("ml_cap_mult:2:20").unwrap()];
+    let result = optimize(&scenarios, &factors, &goal(0, 1), 12, 2).unwrap();
+    assert!(result.best.feasible());
+    // The last iteration can run past the budget by up to k + 2 candidates
+    assert!(result.evaluations <= 12 + 3);
+    assert_eq!(result.best_results.len(), 2);
+    assert_eq!(result.best.objective, result.best_results[0].max_mb as f64);
+}
@@ -0,0 +1,117 @@
+//! Differential test against the original Python model
+//!
+//! The fixtures are per-block output of the reference script for a few
+//! scenarios, regenerated with tests/fixtures/python_parity/generate.py.
+//! Each is replayed with `SimulationConfig::python_reference()`, so parity
+//! can be checked without a Python install.
+
+use wasm_sim::lib_core::{run_simulation_core, SimulationConfig, SimulationResults};
+
+/// Relative tolerance for fees and penalties: the two implementations sum
+/// the same terms, but not always in the same order
+const FLOAT_TOLERANCE: f64 = 1e-9;
+
+struct Fixture {
+    name: &'static str,
+    n: u32,
+    run_type: u32,
+    large_sim_mode: bool,
+    /// Columns: M_B, M_L, M_S, M_N, input_volume, block_fee, penalty
+    rows: Vec<[f64; 7]>,
+}
+
+impl Fixture {
+    fn parse(name: &'static str, text: &str) -> Fixture {
+        let mut lines = text.lines();
+        let settings = lines.next().and_then(|line| line.strip_prefix('#')).expect("settings line");
+        let setting = |key: &str| -> u32 {
+            settings
+                .split_whitespace()
+                .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| panic!("{}: missing {}", name, key))
+        };
+        lines.next(); // column names
+        let rows = lines
+            .map(|line| {
+                let mut row = [0.0; 7];
+                for (cell, value) in row.iter_mut().zip(line.split(',')) {
+                    *cell = value.parse().unwrap_or_else(|_| panic!("{}: bad value '{}'", name, value));
+                }
+                row
+            })
+            .collect();
+        Fixture {
+            name,
+            n: setting("n"),
+            run_type: setting("run_type"),
+            large_sim_mode: setting("large_sim_mode") != 0,
+            rows,
+        }
+    }
+
+    fn run(&self) -> SimulationResults {
+        run_simulation_core(SimulationConfig {
+            n: self.n,
+            run_type: self.run_type,
+            large_sim_mode: self.large_sim_mode,
+            ..SimulationConfig::python_reference()
+        })
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= FLOAT_TOLERANCE * a.abs().max(b.abs()).max(1e-12)
+}
+
+fn check(fixture: Fixture) {
+    let results = fixture.run();
+    assert_eq!(results.M_B.len(), fixture.rows.len(), "{}: block count", fixture.name);
+
+    for (i, expected) in fixture.rows.iter().enumerate() {
+        let sizes = [
+            results.M_B[i],
+            results.M_L[i],
+            results.M_S[i],
+            results.M_N[i],
+            results.input_volume[i],
+        ];
+        for (k, (&actual, column)) in sizes.iter().zip(["M_B", "M_L", "M_S", "M_N", "input_volume"]).enumerate() {
+            assert_eq!(actual as f64, expected[k], "{}: {} at block {}", fixture.name, column, i);
+        }
+        assert!(
+            close(results.block_fee[i], expected[5]),
+            "{}: block_fee at block {}: {} vs {}",
+            fixture.name,
+            i,
+            results.block_fee[i],
+            expected[5]
+        );
+        assert!(
+            close(results.penalty[i], expected[6]),
+            "{}: penalty at block {}: {} vs {}",
+            fixture.name,
+            i,
+            results.penalty[i],
+            expected[6]
+        );
+    }
+}
+
+macro_rules! parity_test {
+    ($test:ident, $file:literal) => {
+        #[test]
+        fn $test() {
+            check(Fixture::parse(
+                $file,
+                include_str!(concat!("fixtures/python_parity/", $file, ".csv")),
+            ));
+        }
+    };
+}
+
+parity_test!(linear_ramp, "linear_ramp");
+parity_test!(fast_linear_ramp_large_sim_mode, "fast_linear_ramp");
+parity_test!(parabolic_ramp_large_sim_mode, "parabolic_ramp");
+parity_test!(max_flood, "max_flood");
+parity_test!(ramp_to_sine_large_sim_mode, "ramp_to_sine");
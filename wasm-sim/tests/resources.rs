@@ -0,0 +1,63 @@
+//! Node resource report: trailing-day bandwidth and the first bottleneck
+
+use wasm_sim::lib_core::{SimulationConfig, SimulationResults};
+use wasm_sim::resources::{Bottleneck, CostProfile, NodeProfile, ResourceReport};
+
+/// Three blocks a day
+const BLOCK_TIME: u32 = 28_800;
+
+/// Every byte is relayed once and verified in a second per byte
+const COSTS: CostProfile = CostProfile {
+    bytes_per_input: 1.0,
+    seconds_per_input: 1.0,
+    seconds_per_byte: 0.0,
+    relay_factor: 1.0,
+};
+
+fn results(timestamps: &[f64]) -> SimulationResults {
+    SimulationResults {
+        M_B: vec![100, 200, 300, 400, 500],
+        indices: (0..5).collect(),
+        timestamps: timestamps.to_vec(),
+        ..SimulationResults::default()
+    }
+}
+
+fn report(results: &SimulationResults, node: NodeProfile) -> ResourceReport {
+    let config = SimulationConfig { block_time: BLOCK_TIME, ..SimulationConfig::default() };
+    ResourceReport::from_results(results, &config, &COSTS, &node)
+}
+
+#[test]
+fn bandwidth_covers_the_trailing_day() {
+    let report = report(&results(&[]), NodeProfile::default());
+    // The first two blocks are scaled up from a third and two thirds of a
+    // day, then the oldest block leaves the window as each new one arrives
+    assert_eq!(report.bandwidth_per_day, vec![300.0, 450.0, 600.0, 900.0, 1200.0]);
+    assert_eq!(report.chain_size, vec![100.0, 300.0, 600.0, 1000.0, 1500.0]);
+    assert_eq!(report.verify_seconds, vec![100.0, 200.0, 300.0, 400.0, 500.0]);
+}
+
+#[test]
+fn bandwidth_follows_the_timestamps() {
+    let report = report(&results(&[10_000.0, 20_000.0, 90_000.0, 100_000.0, 200_000.0]), NodeProfile::default());
+    // The last block comes after a long gap, so only it is in its day
+    assert_eq!(report.bandwidth_per_day, vec![864.0, 1296.0, 600.0, 900.0, 500.0]);
+}
+
+#[test]
+fn reports_the_first_bottleneck() {
+    let results = results(&[]);
+    let node = |bandwidth: f64, storage: f64, cores: f64| NodeProfile {
+        bandwidth,
+        storage,
+        cores,
+        initial_chain_size: 0.0,
+    };
+    // Bandwidth is short from the start but only counts once a full day is in
+    assert_eq!(report(&results, node(0.001, 250.0, 1.0)).falls_behind_at, Some((1, Bottleneck::Storage)));
+    assert_eq!(report(&results, node(0.001, 1e12, 1.0)).falls_behind_at, Some((2, Bottleneck::Bandwidth)));
+    // Verification wins when everything runs out at once
+    assert_eq!(report(&results, node(0.001, 50.0, 0.001)).falls_behind_at, Some((0, Bottleneck::Verification)));
+    assert_eq!(report(&results, node(1.0, 1e12, 1.0)).falls_behind_at, None);
+}
@@ -0,0 +1,63 @@
+//! Worker result buffers: header, base series and the optional groups
+
+use wasm_sim::{
+    get_result_buffer_header_len, get_result_buffer_series, run_simulation_buffer, SimulationConfig, RESULT_SERIES,
+    SERIES_CONSTRAINTS, SERIES_SANITY_CAP, SERIES_TIERS, SERIES_TRACE,
+};
+
+fn config(full_trace: bool) -> Vec<f64> {
+    let mut config = SimulationConfig::new(
+        300, 1_000_000, 1_000_000, 10_000, 0.6, 50_000, 50, 800, 5, 3.0, 2.0, 50.0, false, false, false, false, false,
+        10_000_000, 0.0, true, 10_000_000, 0,
+    );
+    config.full_trace = full_trace;
+    config.serialize()
+}
+
+/// Series of a buffer by name
+fn split(buffer: &[f64]) -> Vec<(String, Vec<f64>)> {
+    let header = get_result_buffer_header_len() as usize;
+    let points = buffer[1] as usize;
+    let names = get_result_buffer_series(buffer[6] as u32);
+    assert_eq!(buffer.len(), header + names.len() * points);
+    names.into_iter().zip(buffer[header..].chunks(points).map(<[f64]>::to_vec)).collect()
+}
+
+#[test]
+fn base_buffer_holds_the_base_series() {
+    let buffer = run_simulation_buffer(&config(false), 0);
+    assert_eq!((buffer[0], buffer[1], buffer[6]), (2.0, 300.0, 0.0));
+    let names: Vec<String> = split(&buffer).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, RESULT_SERIES);
+}
+
+#[test]
+fn optional_groups_follow_the_flags() {
+    let all = SERIES_TIERS | SERIES_SANITY_CAP | SERIES_CONSTRAINTS | SERIES_TRACE;
+
+    // Without full_trace there is no trace to send, and the header says so
+    let buffer = run_simulation_buffer(&config(false), all);
+    assert_eq!(buffer[6] as u32, all & !SERIES_TRACE);
+    let series = split(&buffer);
+    assert_eq!(series.len(), RESULT_SERIES.len() + 7);
+    let get = |series: &[(String, Vec<f64>)], name: &str| series.iter().find(|(n, _)| n == name).unwrap().1.clone();
+    let sanity_cap = get(&series, "sanity_cap");
+    assert_eq!(sanity_cap[0], 10_000_000.0);
+    assert!(sanity_cap.windows(2).all(|w| w[1] >= w[0]));
+    assert!(get(&series, "block_limit").iter().all(|&id| id <= 4.0));
+
+    let buffer = run_simulation_buffer(&config(true), SERIES_CONSTRAINTS | SERIES_TRACE);
+    assert_eq!(buffer[6] as u32, SERIES_CONSTRAINTS | SERIES_TRACE);
+    let series = split(&buffer);
+    assert_eq!(series.len(), RESULT_SERIES.len() + 12);
+    // The block limit is M_B_max whenever the block reached it
+    let (m_b, m_b_max) = (get(&series, "M_B"), get(&series, "m_b_max"));
+    for (limit, (m_b, m_b_max)) in get(&series, "block_limit").iter().zip(m_b.iter().zip(&m_b_max)) {
+        assert_eq!(*limit <= 2.0, m_b >= m_b_max);
+    }
+}
+
+#[test]
+fn rejects_configs_of_another_layout() {
+    assert!(run_simulation_buffer(&[1.0, 2.0], 0).is_empty());
+}
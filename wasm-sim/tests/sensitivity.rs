@@ -0,0 +1,34 @@
+//! Sobol indices on a case where only one factor moves the metric
+
+use wasm_sim::lib_core::{SimulationConfig, SimulationInputs};
+use wasm_sim::sensitivity::{sobol_analysis, Factor, Metric};
+
+#[test]
+fn only_the_moving_factor_gets_the_variance() {
+    // Linear demand z_m + 100*t stays in the penalty free zone, so every block
+    // takes the whole demand and max M_B moves with z_m alone. A_C starts at
+    // 10 MB and never binds, so its growth rate changes nothing.
+    let config = SimulationConfig { n: 500, run_type: 1, simple_blocks: true, ..SimulationConfig::default() };
+    let factors = [
+        Factor::parse("z_m:800000:1200000").unwrap(),
+        Factor::parse("sanity_growth_rate:0.0:0.001").unwrap(),
+    ];
+    let report = sobol_analysis(&config, &SimulationInputs::default(), &factors, 1024, 5_000_000, 7, 2);
+    assert_eq!(report.runs, 1024 * 4);
+
+    let moving = report.index(0, Metric::MaxMb);
+    assert!((moving.first_order - 1.0).abs() < 0.1, "{:?}", moving);
+    assert!((moving.total_order - 1.0).abs() < 0.1, "{:?}", moving);
+    let idle = report.index(1, Metric::MaxMb);
+    assert_eq!((idle.first_order, idle.total_order), (0.0, 0.0));
+}
+
+#[test]
+fn seed_fixes_the_sample() {
+    let config = SimulationConfig { n: 300, run_type: 1, simple_blocks: true, ..SimulationConfig::default() };
+    let factors = [Factor::parse("z_m:800000:1200000").unwrap()];
+    let inputs = SimulationInputs::default();
+    let run = |seed, threads| sobol_analysis(&config, &inputs, &factors, 32, 5_000_000, seed, threads).indices;
+    assert_eq!(run(3, 1), run(3, 4));
+    assert_ne!(run(3, 1), run(4, 1));
+}
@@ -0,0 +1,84 @@
+//! Run summary metrics on hand-built series
+
+use wasm_sim::constraints::BlockLimit;
+use wasm_sim::lib_core::{SimulationConfig, SimulationResults};
+use wasm_sim::summary::{RunSummary, TimeToSize};
+
+const STEADY_STATE: i64 = 1000;
+
+fn summarize(results: &SimulationResults) -> RunSummary {
+    let config = SimulationConfig { steady_state: STEADY_STATE, ..SimulationConfig::default() };
+    RunSummary::from_results(results, &config)
+}
+
+/// Results with every series sampled at heights 0, 10, 20, ...
+fn sampled(m_b: &[i64], mempool: &[i64], block_fee: &[f64], block_limit: &[BlockLimit]) -> SimulationResults {
+    SimulationResults {
+        M_B: m_b.to_vec(),
+        mempool_size: mempool.to_vec(),
+        block_fee: block_fee.to_vec(),
+        block_limit: block_limit.iter().map(|&limit| limit as u8).collect(),
+        indices: (0..m_b.len() as u32).map(|k| 10 * k).collect(),
+        ..SimulationResults::default()
+    }
+}
+
+#[test]
+fn summarizes_a_backlog_that_clears() {
+    use BlockLimit::*;
+    let results = sampled(
+        &[1000, 2000, 2500, 6000, 4000, 1000],
+        &[0, 50, 300, 300, 100, 0],
+        &[1.0, 2.0, 2.5, 60.0, 4.0, 1.0],
+        &[Demand, TwoMn, TwoMn, LongTermCap, Fee, SanityCap],
+    );
+    let summary = summarize(&results);
+
+    assert_eq!(
+        summary.time_to_size,
+        vec![
+            TimeToSize { multiple: 2, blocks: Some(10) },
+            TimeToSize { multiple: 5, blocks: Some(30) },
+            TimeToSize { multiple: 10, blocks: None },
+        ]
+    );
+    // Equal peaks at 20 and 30: the earliest counts, and it clears at 50
+    assert_eq!(summary.mempool_peak_at, 20);
+    assert_eq!(summary.mempool_recovery, Some(30));
+    // Fee per byte is 0.001 everywhere but the 0.01 spike at height 30
+    assert!((summary.fee_spike_ratio - 10.0).abs() < 1e-9);
+    // Blocks 10..=30 reached M_B_max; Fee and Demand end a span
+    assert_eq!((summary.longest_full_span, summary.longest_full_span_start), (3, 10));
+    assert_eq!(summary.backlog_area, 750.0);
+}
+
+#[test]
+fn backlog_left_at_the_end_has_no_recovery() {
+    use BlockLimit::*;
+    let results = sampled(&[1000, 2000, 2000], &[0, 100, 400], &[1.0, 2.0, 2.0], &[Demand, TwoMn, TwoMn]);
+    let summary = summarize(&results);
+    assert_eq!(summary.mempool_peak_at, 20);
+    assert_eq!(summary.mempool_recovery, None);
+    assert_eq!((summary.longest_full_span, summary.longest_full_span_start), (2, 10));
+}
+
+#[test]
+fn quiet_run_has_empty_metrics() {
+    use BlockLimit::*;
+    let results = sampled(&[1000; 4], &[0; 4], &[0.0; 4], &[Demand; 4]);
+    let summary = summarize(&results);
+    assert!(summary.time_to_size.iter().all(|time| time.blocks.is_none()));
+    // An all-zero mempool peaks at the first block and is already clear
+    assert_eq!((summary.mempool_peak_at, summary.mempool_recovery), (0, Some(0)));
+    // No fees paid
+    assert_eq!(summary.fee_spike_ratio, 0.0);
+    assert_eq!((summary.longest_full_span, summary.longest_full_span_start), (0, 0));
+    assert_eq!(summary.backlog_area, 0.0);
+}
+
+#[test]
+fn empty_run_is_summarized() {
+    let summary = summarize(&SimulationResults::default());
+    assert_eq!((summary.mempool_peak_at, summary.mempool_recovery), (0, None));
+    assert_eq!(summary.fee_spike_ratio, 0.0);
+}
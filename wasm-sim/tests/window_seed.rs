@@ -0,0 +1,33 @@
+//! Window dumps: chaining runs through saved median windows
+
+use wasm_sim::lib_core::{run_simulation_with_inputs, SimulationConfig, SimulationInputs};
+use wasm_sim::seed::WindowSeed;
+
+#[test]
+fn dump_round_trips_final_windows() {
+    let config = SimulationConfig { n: 2000, run_type: 5, ..SimulationConfig::default() };
+    let first = run_simulation_with_inputs(config, &SimulationInputs::default());
+    let dump = WindowSeed::from_text(&first.final_windows.to_text()).unwrap();
+    assert_eq!(dump.long_weights, first.final_windows.long_weights);
+    assert_eq!(dump.short_weights, first.final_windows.short_weights);
+    assert_eq!(dump.last_block_weight, first.final_windows.last_block_weight);
+
+    // The next run starts from the medians the first one ended with
+    let inputs = SimulationInputs { window_seed: Some(dump), ..SimulationInputs::default() };
+    let second = run_simulation_with_inputs(config, &inputs);
+    assert!(second.M_S[0] > config.steady_state);
+}
+
+#[test]
+fn rejects_bad_dumps() {
+    let cases = [
+        ("long,1\nshort,1\n", "window dump needs last_block_weight and non-empty long and short lines"),
+        ("last_block_weight,5\nlong\nshort,1\n", "window dump needs last_block_weight and non-empty long and short lines"),
+        ("last_block_weight,5,6\n", "line 1: expected one last block weight"),
+        ("# windows\nlong,1,x\n", "line 2: invalid weight 'x'"),
+        ("medium,1\n", "line 1: unknown key 'medium'"),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(WindowSeed::from_text(text).unwrap_err(), expected, "{:?}", text);
+    }
+}